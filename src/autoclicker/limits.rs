@@ -0,0 +1,231 @@
+use crate::input::{InputBackend, InputResult};
+use enigo::{Button, Direction};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// The file an administrator can set limits in, e.g. `{"max_clicks_per_second": 10}`.
+/// It is not in the state directory, so users can't change it.
+pub const SYSTEM_LIMITS_FILE: &str = "/etc/turbo-clicker/limits.json";
+
+/// Safety limits that apply to every run, whatever started it. 0 turns a limit off.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Limits {
+    pub max_clicks_per_second: u64,
+    /// The longest a run may go on in minutes, the time spent paused does not count.
+    pub max_run_minutes: u64,
+    /// The most clicks until the app is restarted.
+    pub max_session_clicks: u64,
+}
+
+impl Limits {
+    /// The shortest delay between clicks in milliseconds that stays below the clicks per second.
+    pub fn min_delay_ms(&self) -> u64 {
+        match self.max_clicks_per_second {
+            0 => 0,
+            max => 1000u64.div_ceil(max),
+        }
+    }
+
+    /// The longest a run may go on, None if it is not limited.
+    pub fn max_run_time(&self) -> Option<Duration> {
+        (self.max_run_minutes > 0)
+            .then(|| Duration::from_secs(self.max_run_minutes.saturating_mul(60)))
+    }
+
+    /// Load the limits of the administrator, there are none if the file does not exist.
+    pub fn from_system_path<P>(path: P) -> Result<Self, Box<dyn Error>>
+    where
+        P: AsRef<Path>,
+    {
+        if !fs::exists(&path)? {
+            return Ok(Self::default());
+        }
+        let file = fs::File::open(&path)?;
+        Ok(serde_json::from_reader(file)?)
+    }
+
+    /// Apply the limits of the administrator on top, the stricter value of each limit wins.
+    /// A limit the user turned off still applies if the administrator set it.
+    pub fn within(self, system: Limits) -> Limits {
+        let stricter = |user: u64, system: u64| match (user, system) {
+            (0, system) => system,
+            (user, 0) => user,
+            (user, system) => user.min(system),
+        };
+        Limits {
+            max_clicks_per_second: stricter(
+                self.max_clicks_per_second,
+                system.max_clicks_per_second,
+            ),
+            max_run_minutes: stricter(self.max_run_minutes, system.max_run_minutes),
+            max_session_clicks: stricter(self.max_session_clicks, system.max_session_clicks),
+        }
+    }
+
+    /// Describe the limits that are set, empty if there are none.
+    pub fn describe(&self) -> String {
+        let mut limits = Vec::new();
+        if self.max_clicks_per_second > 0 {
+            limits.push(format!("{} clicks per second", self.max_clicks_per_second));
+        }
+        if self.max_run_minutes > 0 {
+            limits.push(format!("{} minutes per run", self.max_run_minutes));
+        }
+        if self.max_session_clicks > 0 {
+            limits.push(format!("{} clicks per session", self.max_session_clicks));
+        }
+        limits.join(", ")
+    }
+}
+
+/// The clicks sent in this session.
+#[derive(Default)]
+struct Clicks {
+    total: u64,
+    /// The times of the clicks within the last second.
+    recent: VecDeque<Instant>,
+}
+
+/// Counts every click to enforce the limits, clones share the same state.
+#[derive(Clone, Default)]
+pub(super) struct ClickGuard {
+    /// The limits of the user, the ones of the administrator are applied on top.
+    limits: Arc<Mutex<Limits>>,
+    system: Arc<Mutex<Limits>>,
+    clicks: Arc<Mutex<Clicks>>,
+    /// Why the current run was stopped by a limit.
+    stop_reason: Arc<Mutex<Option<String>>>,
+    /// Why no run may start, e.g. the limits of the administrator could not be loaded.
+    refusal: Arc<Mutex<Option<String>>>,
+}
+
+impl ClickGuard {
+    pub(super) fn set_limits(&self, limits: Limits) {
+        *self.limits.lock().unwrap() = limits;
+    }
+
+    pub(super) fn set_system_limits(&self, limits: Limits) {
+        *self.system.lock().unwrap() = limits;
+    }
+
+    pub(super) fn refuse(&self, reason: String) {
+        *self.refusal.lock().unwrap() = Some(reason);
+    }
+
+    pub(super) fn refusal(&self) -> Option<String> {
+        self.refusal.lock().unwrap().clone()
+    }
+
+    /// The limits that apply, the stricter ones of the user and the administrator.
+    pub(super) fn limits(&self) -> Limits {
+        self.limits
+            .lock()
+            .unwrap()
+            .within(*self.system.lock().unwrap())
+    }
+
+    /// Count a click, or return the limit it would break.
+    fn admit(&self) -> Result<(), String> {
+        let limits = self.limits();
+        let mut clicks = self.clicks.lock().unwrap();
+        if limits.max_session_clicks > 0 && clicks.total >= limits.max_session_clicks {
+            return Err(format!(
+                "reached the limit of {} clicks per session",
+                limits.max_session_clicks
+            ));
+        }
+        let now = Instant::now();
+        while clicks
+            .recent
+            .front()
+            .is_some_and(|click| now.duration_since(*click) >= Duration::from_secs(1))
+        {
+            clicks.recent.pop_front();
+        }
+        if limits.max_clicks_per_second > 0
+            && clicks.recent.len() as u64 >= limits.max_clicks_per_second
+        {
+            return Err(format!(
+                "exceeded the limit of {} clicks per second",
+                limits.max_clicks_per_second
+            ));
+        }
+        clicks.total += 1;
+        clicks.recent.push_back(now);
+        Ok(())
+    }
+
+    /// Stop the run because it hit a limit, the reason is shown once it has ended.
+    pub(super) fn stop(&self, running: &AtomicBool, reason: String) {
+        eprintln!("Stopping the autoclicker: {reason}");
+        *self.stop_reason.lock().unwrap() = Some(reason);
+        running.store(false, Ordering::Release);
+    }
+
+    /// Return why the run was stopped by a limit and forget it.
+    pub(super) fn take_stop_reason(&self) -> Option<String> {
+        self.stop_reason.lock().unwrap().take()
+    }
+}
+
+/// Wraps the input backend, so clicks past a limit stop the run instead of being sent.
+pub(super) struct LimitedInput {
+    backend: Box<dyn InputBackend>,
+    guard: ClickGuard,
+    running: Arc<AtomicBool>,
+}
+
+impl LimitedInput {
+    pub(super) fn new(
+        backend: Box<dyn InputBackend>,
+        guard: ClickGuard,
+        running: Arc<AtomicBool>,
+    ) -> Self {
+        Self {
+            backend,
+            guard,
+            running,
+        }
+    }
+}
+
+impl InputBackend for LimitedInput {
+    fn button(&mut self, button: Button, direction: Direction) -> InputResult<()> {
+        // Held buttons always need to be released.
+        if direction != Direction::Release
+            && let Err(reason) = self.guard.admit()
+        {
+            self.guard.stop(&self.running, reason.clone());
+            return Err(reason.into());
+        }
+        self.backend.button(button, direction)
+    }
+
+    fn move_by(&mut self, dx: i32, dy: i32) -> InputResult<()> {
+        self.backend.move_by(dx, dy)
+    }
+
+    fn move_to(&mut self, x: i32, y: i32) -> InputResult<()> {
+        self.backend.move_to(x, y)
+    }
+
+    fn key(&mut self, code: u16, direction: Direction) -> InputResult<()> {
+        self.backend.key(code, direction)
+    }
+
+    fn location(&self) -> InputResult<(i32, i32)> {
+        self.backend.location()
+    }
+
+    fn screen_size(&self) -> InputResult<(i32, i32)> {
+        self.backend.screen_size()
+    }
+}
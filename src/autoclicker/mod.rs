@@ -2,131 +2,858 @@ use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::time::Duration;
 
-use enigo::{Button, Coordinate, Direction, Enigo, Mouse, Settings};
+use enigo::{Button, Direction};
 use tokio::sync::Mutex;
-use tokio::time::sleep;
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::sync::watch;
+use tokio::time::{Instant, sleep};
 
-use futures_util::StreamExt;
-
-use crate::hotkey::HotkeyPortal;
+use crate::failsafe::Failsafe;
+use crate::hotkey::{HotkeyAction, HotkeyEvent, HotkeyMode, HotkeySettings, RunTiming};
+use crate::idle::{IdleMonitor, KeepaliveAction};
+use crate::input::{BackendKind, InputBackend, InputResult, create_backend};
+use crate::macros::{Macro, MacroAction};
+use crate::script::Program;
 
+mod limits;
 #[cfg(test)]
 mod test;
 
+use limits::{ClickGuard, LimitedInput};
+pub use limits::{Limits, SYSTEM_LIMITS_FILE};
+
+/// The virtual input shared between the autoclicker and its running task.
+pub type SharedInput = Arc<Mutex<Option<Box<dyn InputBackend>>>>;
+
+/// The button the autoclicker clicks.
+const CLICK_BUTTON: Button = Button::Left;
+
+/// How often the keep-alive mode checks whether the user is inactive.
+const KEEPALIVE_POLL_MS: u64 = 250;
+
+/// What the autoclicker is currently doing.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Status {
+    #[default]
+    Idle,
+    /// Waiting for the start delay, with the seconds left.
+    Countdown(u64),
+    /// The start delay was cancelled before clicking started.
+    Aborted,
+    Clicking,
+    Playing,
+    Scripting,
+    KeepingAlive,
+    /// The run is paused until it is resumed.
+    Paused,
+    /// The run stopped because of an error.
+    Failed(String),
+}
+
+impl Status {
+    /// Describe the status for the UI.
+    pub fn describe(&self) -> String {
+        match self {
+            Status::Idle => String::new(),
+            Status::Countdown(remaining) => format!("Starting in {remaining} s"),
+            Status::Aborted => "Start delay aborted".to_string(),
+            Status::Clicking => "Clicking".to_string(),
+            Status::Playing => "Playing macro".to_string(),
+            Status::Scripting => "Running script".to_string(),
+            Status::KeepingAlive => "Keeping the session alive while you are inactive".to_string(),
+            Status::Paused => "Paused".to_string(),
+            Status::Failed(error) => format!("Stopped: {error}"),
+        }
+    }
+}
+
 /// Implement the autoclicker functionality
 #[derive(Clone)]
 pub struct Autoclicker {
-    enigo: Arc<Mutex<Enigo>>,
+    input: SharedInput,
     running: Arc<AtomicBool>,
     stopped: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
+    /// The id of the latest run, tasks of earlier runs use it to notice that they are stale.
+    run_id: Arc<std::sync::Mutex<u64>>,
+    status: watch::Sender<Status>,
+    excluded_button: Arc<std::sync::Mutex<Option<Button>>>,
+    failsafe: Failsafe,
+    click_guard: ClickGuard,
 }
 
 impl Autoclicker {
-    /// Create a new Autoclicker instance.
-    /// This will initialize the enigo instance for virtual input.
-    /// Returns an error if the enigo instance cannot be created.
-    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
-        let mut enigo = Enigo::new(&Settings::default())?;
-
-        // Move the mouse slightly to ensure the permission prompt is triggered.
-        enigo.move_mouse(0, 0, Coordinate::Rel)?;
-
-        Ok(Autoclicker {
-            enigo: Arc::new(Mutex::new(enigo)),
-            running: Arc::new(AtomicBool::new(false)),
+    /// Create a new Autoclicker instance without virtual input.
+    /// It can't be started until the input has been initialized with `init_input`.
+    pub fn new() -> Self {
+        Self::from_input(None)
+    }
+
+    /// Create a new Autoclicker instance using the given backend for virtual input.
+    #[cfg(test)]
+    pub fn with_backend<B>(backend: B) -> Self
+    where
+        B: InputBackend + 'static,
+    {
+        Self::from_input(Some(Box::new(backend)))
+    }
+
+    fn from_input(input: Option<Box<dyn InputBackend>>) -> Self {
+        let running = Arc::new(AtomicBool::new(false));
+        let click_guard = ClickGuard::default();
+        let input = input.map(|backend| limit_input(backend, &click_guard, &running));
+        Autoclicker {
+            input: Arc::new(Mutex::new(input)),
+            running,
             stopped: Arc::new(AtomicBool::new(true)),
-        })
+            paused: Arc::new(AtomicBool::new(false)),
+            run_id: Arc::new(std::sync::Mutex::new(0)),
+            status: watch::Sender::new(Status::Idle),
+            excluded_button: Arc::new(std::sync::Mutex::new(None)),
+            failsafe: Failsafe::default(),
+            click_guard,
+        }
+    }
+
+    /// Initialize the given backend for virtual input, replacing the current one.
+    /// On error the previous backend is dropped, so the autoclicker stays disabled until it succeeds.
+    pub async fn init_input(&self, kind: BackendKind) -> InputResult<()> {
+        let mut input = self.input.lock().await;
+        // Drop the old backend first, otherwise two uinput devices would exist at the same time.
+        *input = None;
+        // Initialization might wait for the user to answer a permission prompt.
+        let backend = tokio::task::spawn_blocking(move || create_backend(kind)).await??;
+        *input = Some(limit_input(backend, &self.click_guard, &self.running));
+        Ok(())
     }
 
     /// Start the autoclicker with the given delay in milliseconds between clicks.
     /// If a start delay (in seconds) is provided, it will wait for it before starting.
+    /// The start delay counts as running, stopping the autoclicker during it aborts the start.
     /// If a duration (in seconds) is provided, it will stop the autoclicker after that duration.
-    /// Returns true if the autoclicker was started, false if it was already running.
+    /// Returns true if the autoclicker was started, false if it was already running, has no input
+    /// or would click its own trigger.
     pub async fn autoclick(
         &mut self,
         delay_ms: Arc<AtomicU64>,
         start_delay: Option<u64>,
         duration: Option<u64>,
     ) -> bool {
+        if self.is_excluded(CLICK_BUTTON) {
+            eprintln!("{CLICK_BUTTON:?} triggers the autoclicker, not clicking it");
+            return false;
+        }
+        let failsafe = self.failsafe.clone();
+        let click_guard = self.click_guard.clone();
+        self.start(
+            start_delay,
+            duration,
+            Status::Clicking,
+            |input, running| async move {
+                // Not every backend can report the pointer position, it is only informational.
+                let position = match input.lock().await.as_ref().map(|input| input.location()) {
+                    Some(Ok((x, y))) => format!(" at ({x}, {y})"),
+                    _ => String::new(),
+                };
+                println!(
+                    "Autoclicker started{position} with delay: {} ms",
+                    delay_ms.load(Ordering::Relaxed)
+                );
+                while running.load(Ordering::Relaxed) {
+                    let mut input = input.lock().await;
+                    // The run might have been stopped while it was paused.
+                    if !running.load(Ordering::Acquire) {
+                        break;
+                    }
+                    match input.as_mut() {
+                        Some(input) => {
+                            failsafe.check(input.as_ref())?;
+                            if let Err(e) = input.button(CLICK_BUTTON, Direction::Click) {
+                                eprintln!("Failed to click mouse button: {e}");
+                            }
+                        }
+                        None => {
+                            eprintln!("Virtual input is no longer available, stopping autoclicker");
+                            running.store(false, Ordering::Release);
+                        }
+                    }
+                    drop(input);
+
+                    // The clicks per second limit is a ceiling for the delay.
+                    let delay = delay_ms
+                        .load(Ordering::Acquire)
+                        .max(click_guard.limits().min_delay_ms());
+                    let mut elapsed_time_ms = 0;
+                    while running.load(Ordering::Relaxed) && elapsed_time_ms < delay {
+                        sleep(Duration::from_millis(10)).await;
+                        elapsed_time_ms += 10;
+                    }
+                }
+                Ok(())
+            },
+        )
+        .await
+    }
+
+    /// Replay the macro through the virtual input, with its delays scaled by `speed` in percent.
+    /// The macro is repeated `loops` times, or until stopped if `loops` is 0.
+    /// Start delay and duration work the same as for `autoclick`.
+    /// Buttons and keys still held when the replay ends are released.
+    /// Returns true if the replay was started, false if it was already running, has no input
+    /// or the macro is empty.
+    pub async fn play_macro(
+        &mut self,
+        recorded: Arc<Macro>,
+        speed: u64,
+        loops: u64,
+        start_delay: Option<u64>,
+        duration: Option<u64>,
+    ) -> bool {
+        if recorded.events.is_empty() {
+            eprintln!("The macro is empty, not playing it");
+            return false;
+        }
+        let speed = speed.max(1);
+        let excluded = *self.excluded_button.lock().unwrap();
+        let failsafe = self.failsafe.clone();
+        self.start(
+            start_delay,
+            duration,
+            Status::Playing,
+            move |input, running| async move {
+                println!("Playing macro {loops} times at {speed} % speed");
+                let mut held = Vec::new();
+                let mut played = 0;
+                let mut tripped = None;
+                'replay: while loops == 0 || played < loops {
+                    for event in &recorded.events {
+                        if !wait(event.delay_ms.saturating_mul(100) / speed, &running).await {
+                            break 'replay;
+                        }
+                        if let MacroAction::Button { button, .. } = event.action
+                            && Some(button) == excluded
+                        {
+                            continue;
+                        }
+                        let mut input = input.lock().await;
+                        if !running.load(Ordering::Acquire) {
+                            break 'replay;
+                        }
+                        let Some(input) = input.as_mut() else {
+                            eprintln!("Virtual input is no longer available, stopping macro");
+                            break 'replay;
+                        };
+                        if let Err(reason) = failsafe.check(input.as_ref()) {
+                            tripped = Some(reason);
+                            break 'replay;
+                        }
+                        if let Err(e) = event.action.play(input.as_mut()) {
+                            eprintln!("Failed to play macro action: {e}");
+                        }
+                        track_held(&mut held, event.action);
+                    }
+                    played += 1;
+                }
+                if let Some(input) = input.lock().await.as_mut() {
+                    for release in held {
+                        if let Err(e) = release.play(input.as_mut()) {
+                            eprintln!("Failed to release held input: {e}");
+                        }
+                    }
+                }
+                match tripped {
+                    Some(reason) => Err(reason),
+                    None => Ok(()),
+                }
+            },
+        )
+        .await
+    }
+
+    /// Run the script through the virtual input until it is done.
+    /// Start delay and duration work the same as for `autoclick`.
+    /// If a command fails the script stops and the status shows the error.
+    /// Returns true if the script was started, false if it was already running or has no input.
+    pub async fn run_script(
+        &mut self,
+        script: Arc<Program>,
+        start_delay: Option<u64>,
+        duration: Option<u64>,
+    ) -> bool {
+        let excluded = *self.excluded_button.lock().unwrap();
+        let failsafe = self.failsafe.clone();
+        self.start(
+            start_delay,
+            duration,
+            Status::Scripting,
+            move |input, running| async move {
+                println!("Running script");
+                // The script sends input on its own, so the failsafe is checked beside it.
+                let script_run = script.run(Arc::clone(&input), Arc::clone(&running), excluded);
+                tokio::pin!(script_run);
+                tokio::select! {
+                    res = &mut script_run => res,
+                    reason = guard_failsafe(&failsafe, &input, &running) => {
+                        // A Rhai script keeps running on its own thread until it notices that
+                        // running is false, the run is only over once it has ended.
+                        if let Err(e) = script_run.await {
+                            eprintln!("Script failed while stopping: {e}");
+                        }
+                        Err(reason)
+                    }
+                }
+            },
+        )
+        .await
+    }
+
+    /// Act through the virtual input every time the user has been inactive for `idle`,
+    /// so the session is not locked. Nothing is sent while the user is active.
+    /// Runs until stopped, the start delay and duration don't apply.
+    /// Returns true if the keep-alive mode was started, false if it was already running or has no input.
+    pub async fn keep_alive(
+        &mut self,
+        monitor: IdleMonitor,
+        idle: Duration,
+        action: KeepaliveAction,
+    ) -> bool {
+        let failsafe = self.failsafe.clone();
+        self.start(
+            None,
+            None,
+            Status::KeepingAlive,
+            move |input, running| async move {
+                println!(
+                    "Keeping alive with {action:?} after {} s of inactivity",
+                    idle.as_secs()
+                );
+                // Our own input might count as user input, so the time since the last action is the upper limit.
+                let mut last_action = Instant::now();
+                while wait(KEEPALIVE_POLL_MS, &running).await {
+                    let idle_time = monitor
+                        .idle_time()
+                        .await
+                        .map_err(|e| format!("failed to get the idle time: {e}"))?;
+                    if idle_time.min(last_action.elapsed()) < idle {
+                        continue;
+                    }
+                    let mut input = input.lock().await;
+                    if !running.load(Ordering::Acquire) {
+                        break;
+                    }
+                    let input = input
+                        .as_mut()
+                        .ok_or("virtual input is no longer available")?;
+                    failsafe.check(input.as_ref())?;
+                    let res = match action {
+                        KeepaliveAction::Nudge => {
+                            input.move_by(1, 0).and_then(|()| input.move_by(-1, 0))
+                        }
+                        KeepaliveAction::Click => input.button(CLICK_BUTTON, Direction::Click),
+                    };
+                    res.map_err(|e| format!("failed to keep alive: {e}"))?;
+                    last_action = Instant::now();
+                }
+                Ok(())
+            },
+        )
+        .await
+    }
+
+    /// Run `work` in the background, after the start delay and until the duration is over.
+    /// `work` receives the virtual input and should return once running is false.
+    /// While the run is paused `work` can't lock the input, so it has to check running again after locking it.
+    /// Returns false if the autoclicker is already running, has no input or refuses runs.
+    async fn start<F, Fut>(
+        &mut self,
+        start_delay: Option<u64>,
+        duration: Option<u64>,
+        working: Status,
+        work: F,
+    ) -> bool
+    where
+        F: FnOnce(SharedInput, Arc<AtomicBool>) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), String>> + Send,
+    {
         let running = Arc::clone(&self.running);
         let stopped = Arc::clone(&self.stopped);
-        if self.is_running() || !self.is_stopped() {
+        if let Some(reason) = self.click_guard.refusal() {
+            eprintln!("Not starting autoclicker: {reason}");
+            self.status.send_replace(Status::Failed(reason));
             return false;
         }
-        running.store(true, Ordering::SeqCst);
-        stopped.store(false, Ordering::SeqCst);
-
-        if let Some(start_delay) = start_delay {
-            println!("Waiting for {start_delay} s before starting autoclicker");
-            sleep(Duration::from_secs(start_delay)).await;
+        // Claim the run before waiting for anything, so two starts at the same time can't both succeed.
+        let run = {
+            let mut run_id = self.run_id.lock().unwrap();
+            if !self.is_stopped()
+                || running
+                    .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+                    .is_err()
+            {
+                return false;
+            }
+            stopped.store(false, Ordering::SeqCst);
+            *run_id += 1;
+            *run_id
+        };
+        if !self.is_ready().await {
+            eprintln!("Virtual input is not initialized, not starting autoclicker");
+            running.store(false, Ordering::SeqCst);
+            stopped.store(true, Ordering::SeqCst);
+            return false;
         }
+        self.failsafe.reset();
+        self.click_guard.take_stop_reason();
 
-        let enigo = Arc::clone(&self.enigo);
+        let input = Arc::clone(&self.input);
+        let status = self.status.clone();
+        let paused = Arc::clone(&self.paused);
+        let run_id = Arc::clone(&self.run_id);
+        let click_guard = self.click_guard.clone();
+        let limits = click_guard.limits();
 
         tokio::spawn(async move {
-            println!(
-                "Autoclicker started with delay: {} ms",
-                delay_ms.load(Ordering::Relaxed)
-            );
-            while running.load(Ordering::Relaxed) {
-                if let Err(e) = enigo.lock().await.button(Button::Left, Direction::Click) {
-                    eprintln!("Failed to click mouse button: {e}");
-                };
+            let _guard = RunGuard {
+                running: Arc::clone(&running),
+                stopped,
+                status: status.clone(),
+            };
+            if let Some(start_delay) = start_delay {
+                println!("Waiting for {start_delay} s before starting autoclicker");
+                if !countdown(start_delay, &running, &status).await {
+                    println!("Start delay aborted");
+                    status.send_replace(Status::Aborted);
+                    return;
+                }
+            }
 
-                let mut elapsed_time_ms = 0;
-                while running.load(Ordering::Relaxed)
-                    && elapsed_time_ms < delay_ms.load(Ordering::Acquire)
-                {
-                    sleep(Duration::from_millis(10)).await;
-                    elapsed_time_ms += 10;
+            if let Some((after, reason)) = run_timer(duration, &limits) {
+                let running = Arc::clone(&running);
+                let click_guard = click_guard.clone();
+                tokio::spawn(async move {
+                    println!("Autoclicker will stop after {} s", after.as_secs());
+                    stop_after(after, run, &run_id, &running, &paused, || match reason {
+                        Some(reason) => click_guard.stop(&running, reason),
+                        None => running.store(false, Ordering::Release),
+                    })
+                    .await;
+                });
+            }
+
+            status.send_replace(working);
+            let result = work(input, Arc::clone(&running)).await;
+            // The work might have finished on its own, e.g. a macro that was played to the end.
+            running.store(false, Ordering::Release);
+            let result = match click_guard.take_stop_reason() {
+                Some(reason) => Err(reason),
+                None => result,
+            };
+            match result {
+                Ok(()) => {
+                    status.send_replace(Status::Idle);
+                    println!("Autoclicker stopped");
+                }
+                Err(e) => {
+                    eprintln!("Autoclicker stopped: {e}");
+                    status.send_replace(Status::Failed(e));
                 }
             }
-            stopped.store(true, Ordering::Release);
-            println!("Autoclicker stopped");
         });
 
-        if let Some(duration) = duration {
-            let running = Arc::clone(&self.running);
-            tokio::spawn(async move {
-                println!("Autoclicker will stop after {duration} s");
-                sleep(Duration::from_secs(duration)).await;
-                running.store(false, Ordering::Release);
-            });
-        }
-
         true
     }
 
-    /// Listen to the event stream and trigger the autoclicker on each event.
-    pub fn trigger_on_hotkey(&self, portal: HotkeyPortal, delay_ms: Arc<AtomicU64>) {
-        let portal = portal.clone();
+    /// Listen to the hotkey events and run the action of the pressed shortcut.
+    /// Runs use the start delay and duration the hotkey settings have for their trigger.
+    /// In hold mode the toggle shortcut and the mouse button click until they are released.
+    /// Actions that don't concern the autoclicker itself are passed on to `on_other_action`.
+    pub fn trigger_on_hotkey<F>(
+        &self,
+        mut events: UnboundedReceiver<HotkeyEvent>,
+        delay_ms: Arc<AtomicU64>,
+        hotkey_settings: Arc<std::sync::Mutex<HotkeySettings>>,
+        on_other_action: F,
+    ) where
+        F: Fn(HotkeyAction) + Send + 'static,
+    {
         let mut autoclicker = self.clone();
         tokio::spawn(async move {
-            let mut stream = match portal.activated_stream().await {
-                Ok(s) => s,
-                Err(e) => {
-                    eprintln!("Failed to receive hotkey activation events: {e}");
-                    return;
+            while let Some(event) = events.recv().await {
+                println!("Hotkey event: {event:?}");
+                let settings = *hotkey_settings.lock().unwrap();
+                if let Some(action) = autoclicker
+                    .handle_hotkey_event(event, &settings, &delay_ms)
+                    .await
+                {
+                    on_other_action(action);
                 }
-            };
-            while stream.next().await.is_some() {
-                println!("Hotkey activated");
-                let started = autoclicker
-                    .autoclick(Arc::clone(&delay_ms), None, None)
+            }
+        });
+    }
+
+    /// Handle a press or release of a hotkey.
+    /// Returns the action if it is not handled by the autoclicker.
+    async fn handle_hotkey_event(
+        &mut self,
+        event: HotkeyEvent,
+        settings: &HotkeySettings,
+        delay_ms: &Arc<AtomicU64>,
+    ) -> Option<HotkeyAction> {
+        match (event, settings.mode) {
+            (
+                HotkeyEvent::Pressed(action @ (HotkeyAction::Toggle | HotkeyAction::MouseToggle)),
+                HotkeyMode::Hold,
+            ) => {
+                self.start_from_hotkey(settings.timing(action), delay_ms)
+                    .await;
+            }
+            (
+                HotkeyEvent::Released(HotkeyAction::Toggle | HotkeyAction::MouseToggle),
+                HotkeyMode::Hold,
+            ) => self.stop(),
+            (HotkeyEvent::Pressed(action), _) => {
+                if !self.run_hotkey_action(action, settings, delay_ms).await {
+                    return Some(action);
+                }
+            }
+            (HotkeyEvent::Released(_), _) => (),
+        }
+        None
+    }
+
+    /// Run the given hotkey action if it controls the autoclicker.
+    /// Returns false if the action is not handled by the autoclicker.
+    async fn run_hotkey_action(
+        &mut self,
+        action: HotkeyAction,
+        settings: &HotkeySettings,
+        delay_ms: &Arc<AtomicU64>,
+    ) -> bool {
+        match action {
+            HotkeyAction::Start => {
+                self.start_from_hotkey(settings.timing(action), delay_ms)
+                    .await;
+            }
+            HotkeyAction::Stop => self.stop(),
+            HotkeyAction::Pause => self.toggle_pause().await,
+            HotkeyAction::Toggle | HotkeyAction::MouseToggle => {
+                let started = self
+                    .start_from_hotkey(settings.timing(action), delay_ms)
                     .await;
                 if !started {
-                    autoclicker.running.store(false, Ordering::Release);
+                    self.stop();
                 }
             }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Start the autoclicker with the start delay and duration of the trigger.
+    async fn start_from_hotkey(&mut self, timing: RunTiming, delay_ms: &Arc<AtomicU64>) -> bool {
+        self.autoclick(Arc::clone(delay_ms), timing.start_delay, timing.duration)
+            .await
+    }
+
+    /// Stop the autoclicker, or abort the start delay if it has not started clicking yet.
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::Release);
+    }
+
+    /// Stop the current run and wait for it to end, so it releases the buttons and keys it holds.
+    /// Then the virtual input is dropped, which releases anything the backend still holds.
+    /// Gives up waiting after `timeout`, the app is quitting anyway.
+    pub async fn shutdown(&self, timeout: Duration) {
+        self.stop();
+        let release = async {
+            self.wait_until_stopped().await;
+            *self.input.lock().await = None;
+        };
+        if tokio::time::timeout(timeout, release).await.is_err() {
+            eprintln!("The autoclicker did not stop in time, quitting anyway");
+        }
+    }
+
+    /// Pause the current run, no input is sent and its duration does not count until `resume`.
+    /// Pausing holds the virtual input, so every kind of run waits at its next input.
+    /// Returns false if nothing is running, it is already paused or the start delay is still counting down.
+    pub async fn pause(&self) -> bool {
+        let working = self.status.borrow().clone();
+        if !self.is_running() || matches!(working, Status::Countdown(_) | Status::Paused) {
+            return false;
+        }
+        if self.paused.swap(true, Ordering::SeqCst) {
+            return false;
+        }
+        let held_input = Arc::clone(&self.input).lock_owned().await;
+        println!("Autoclicker paused");
+        self.status.send_replace(Status::Paused);
+
+        let paused = Arc::clone(&self.paused);
+        let running = Arc::clone(&self.running);
+        let status = self.status.clone();
+        tokio::spawn(async move {
+            while paused.load(Ordering::Acquire) && running.load(Ordering::Acquire) {
+                sleep(Duration::from_millis(10)).await;
+            }
+            paused.store(false, Ordering::Release);
+            // The run might have ended while paused, then its final status has to stay.
+            status.send_if_modified(|status| {
+                let resumed = *status == Status::Paused;
+                if resumed {
+                    *status = working;
+                }
+                resumed
+            });
+            drop(held_input);
+            println!("Autoclicker resumed");
         });
+        true
+    }
+
+    /// Continue the paused run where it left off.
+    /// Returns false if it is not paused.
+    pub fn resume(&self) -> bool {
+        self.paused.swap(false, Ordering::SeqCst)
+    }
+
+    /// Pause the current run, or resume it if it is paused.
+    pub async fn toggle_pause(&self) {
+        if self.is_paused() {
+            self.resume();
+        } else {
+            self.pause().await;
+        }
+    }
+
+    /// Never click the given button, because it triggers the autoclicker.
+    pub fn exclude_button(&self, button: Option<Button>) {
+        *self.excluded_button.lock().unwrap() = button;
+    }
+
+    /// Check if the button must not be clicked, because it triggers the autoclicker.
+    pub fn is_excluded(&self, button: Button) -> bool {
+        *self.excluded_button.lock().unwrap() == Some(button)
+    }
+
+    /// Use the given safety limits for the next runs.
+    pub fn set_limits(&self, limits: Limits) {
+        self.click_guard.set_limits(limits);
+    }
+
+    /// Use the limits of the administrator, the limits of the user can only be stricter.
+    pub fn set_system_limits(&self, limits: Limits) {
+        self.click_guard.set_system_limits(limits);
+    }
+
+    /// Refuse every run from now on, e.g. because the limits of the administrator could not be loaded.
+    pub fn refuse_runs(&self, reason: String) {
+        self.click_guard.refuse(reason);
+    }
+
+    /// The failsafe that stops every run in an emergency.
+    pub fn failsafe(&self) -> &Failsafe {
+        &self.failsafe
+    }
+
+    /// Watch the status of the autoclicker.
+    pub fn watch_status(&self) -> watch::Receiver<Status> {
+        self.status.subscribe()
+    }
+
+    /// Wait until the current run has stopped.
+    pub async fn wait_until_stopped(&self) {
+        while !self.is_stopped() {
+            sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    /// Check if the virtual input can report the pointer position, the failsafe corner needs it.
+    pub async fn can_locate_pointer(&self) -> bool {
+        self.input
+            .lock()
+            .await
+            .as_ref()
+            .is_some_and(|input| input.location().is_ok())
+    }
+
+    /// Check if virtual input has been initialized.
+    pub async fn is_ready(&self) -> bool {
+        self.input.lock().await.is_some()
     }
 
     /// Check if the autoclicker is currently running.
     pub fn is_running(&self) -> bool {
         self.running.load(Ordering::SeqCst)
     }
+    /// Check if the current run is paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
     /// Check if the autoclicker is currently stopped.
     pub fn is_stopped(&self) -> bool {
         self.stopped.load(Ordering::SeqCst)
     }
 }
+
+/// When to stop a run with the duration in seconds, and the reason if it is stopped by the run time limit.
+/// The limit wins over a longer duration.
+fn run_timer(duration: Option<u64>, limits: &Limits) -> Option<(Duration, Option<String>)> {
+    match (duration.map(Duration::from_secs), limits.max_run_time()) {
+        (Some(duration), Some(max)) if duration <= max => Some((duration, None)),
+        (_, Some(max)) => Some((
+            max,
+            Some(format!(
+                "reached the limit of {} min per run",
+                limits.max_run_minutes
+            )),
+        )),
+        (duration, None) => duration.map(|duration| (duration, None)),
+    }
+}
+
+/// Wrap the backend, so its clicks are counted and checked against the limits.
+fn limit_input(
+    backend: Box<dyn InputBackend>,
+    click_guard: &ClickGuard,
+    running: &Arc<AtomicBool>,
+) -> Box<dyn InputBackend> {
+    Box::new(LimitedInput::new(
+        backend,
+        click_guard.clone(),
+        Arc::clone(running),
+    ))
+}
+
+/// Check the failsafe until it triggers, then stop the run and return the reason.
+/// Never returns if the run is stopped otherwise.
+async fn guard_failsafe(failsafe: &Failsafe, input: &SharedInput, running: &AtomicBool) -> String {
+    while running.load(Ordering::Acquire) {
+        if let Some(input) = input.lock().await.as_ref()
+            && let Err(reason) = failsafe.check(input.as_ref())
+        {
+            running.store(false, Ordering::Release);
+            return reason;
+        }
+        sleep(Duration::from_millis(10)).await;
+    }
+    std::future::pending().await
+}
+
+/// Marks the run as stopped once its task ends, even if `work` panicked,
+/// so a failed run can't keep the autoclicker from starting again.
+struct RunGuard {
+    running: Arc<AtomicBool>,
+    stopped: Arc<AtomicBool>,
+    status: watch::Sender<Status>,
+}
+
+impl Drop for RunGuard {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Release);
+        if std::thread::panicking() {
+            eprintln!("Autoclicker stopped: the run panicked");
+            self.status
+                .send_replace(Status::Failed("the run panicked".to_string()));
+        }
+        self.stopped.store(true, Ordering::Release);
+    }
+}
+
+/// Stop the run with the id `run` by calling `stop` once it ran for `duration`,
+/// the time spent paused does not count.
+/// Does nothing if the run has ended before, so a later run is never stopped by it.
+async fn stop_after<F>(
+    duration: Duration,
+    run: u64,
+    run_id: &std::sync::Mutex<u64>,
+    running: &AtomicBool,
+    paused: &AtomicBool,
+    stop: F,
+) where
+    F: FnOnce(),
+{
+    let is_current = || *run_id.lock().unwrap() == run && running.load(Ordering::Acquire);
+    let mut left = duration;
+    while !left.is_zero() {
+        if !is_current() {
+            return;
+        }
+        let tick = Instant::now();
+        sleep(left.min(Duration::from_millis(10))).await;
+        if !paused.load(Ordering::Acquire) {
+            left = left.saturating_sub(tick.elapsed());
+        }
+    }
+    // A new run can't be started while the id is locked.
+    let current = run_id.lock().unwrap();
+    if *current == run {
+        stop();
+    }
+}
+
+/// Count down the start delay in seconds, reporting the seconds left.
+/// Returns false if the autoclicker was stopped before the countdown finished.
+async fn countdown(seconds: u64, running: &AtomicBool, status: &watch::Sender<Status>) -> bool {
+    let end = Instant::now() + Duration::from_secs(seconds);
+    loop {
+        if !running.load(Ordering::Acquire) {
+            return false;
+        }
+        let left = end.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return true;
+        }
+        status.send_if_modified(|status| {
+            let countdown = Status::Countdown(left.as_secs_f64().ceil() as u64);
+            let changed = *status != countdown;
+            *status = countdown;
+            changed
+        });
+        sleep(left.min(Duration::from_millis(10))).await;
+    }
+}
+
+/// Wait for the given time in milliseconds.
+/// Returns false if the autoclicker was stopped before the time was over.
+pub async fn wait(ms: u64, running: &AtomicBool) -> bool {
+    // A delay too long to represent lasts until the run is stopped.
+    let end = Instant::now().checked_add(Duration::from_millis(ms));
+    loop {
+        if !running.load(Ordering::Acquire) {
+            return false;
+        }
+        let left = end.map_or(Duration::MAX, |end| {
+            end.saturating_duration_since(Instant::now())
+        });
+        if left.is_zero() {
+            return true;
+        }
+        sleep(left.min(Duration::from_millis(10))).await;
+    }
+}
+
+/// Keep track of the buttons and keys the macro holds down, as the releases that free them.
+fn track_held(held: &mut Vec<MacroAction>, action: MacroAction) {
+    let release = match action {
+        MacroAction::Button {
+            button,
+            direction: Direction::Press | Direction::Release,
+        } => MacroAction::Button {
+            button,
+            direction: Direction::Release,
+        },
+        MacroAction::Key {
+            code,
+            direction: Direction::Press | Direction::Release,
+        } => MacroAction::Key {
+            code,
+            direction: Direction::Release,
+        },
+        _ => return,
+    };
+    held.retain(|held| *held != release);
+    if release != action {
+        held.push(release);
+    }
+}
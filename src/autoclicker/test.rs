@@ -1,8 +1,21 @@
 use super::*;
+use crate::failsafe::{ESCAPE_PRESSES, FailsafeCorner};
+use crate::input::DeviceReader;
+use crate::input::recording::{InputEvent, RecordingBackend};
+use crate::macros::MacroEvent;
+use crate::script::Language;
 
 #[tokio::test]
 async fn new_autoclicker() {
-    let autoclicker = Autoclicker::new().expect("Failed to create autoclicker");
+    let autoclicker = Autoclicker::new();
+    assert!(
+        !autoclicker.is_ready().await,
+        "Should not be ready before initializing input"
+    );
+    autoclicker
+        .init_input(BackendKind::Enigo)
+        .await
+        .expect("Failed to initialize input");
 
     assert!(
         !autoclicker.running.load(Ordering::SeqCst),
@@ -13,16 +26,16 @@ async fn new_autoclicker() {
         "stopped should be true"
     );
 
-    let enigo = autoclicker.enigo.lock().await;
+    let input = autoclicker.input.lock().await;
     assert!(
-        enigo.location().is_ok(),
+        input.as_ref().unwrap().location().is_ok(),
         "Should be able to get mouse location"
     );
 }
 
-#[tokio::test]
+#[tokio::test(start_paused = true)]
 async fn autoclick_should_stop_when_signaled() {
-    let mut autoclicker = Autoclicker::new().expect("Failed to create autoclicker");
+    let mut autoclicker = Autoclicker::with_backend(RecordingBackend::new());
     let delay_ms = Arc::new(AtomicU64::new(20));
 
     let started = autoclicker
@@ -51,8 +64,39 @@ async fn autoclick_should_stop_when_signaled() {
 }
 
 #[tokio::test]
+async fn can_locate_pointer_needs_input() {
+    assert!(
+        Autoclicker::with_backend(RecordingBackend::new())
+            .can_locate_pointer()
+            .await
+    );
+    assert!(
+        !Autoclicker::new().can_locate_pointer().await,
+        "Without input the pointer position is not known"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn autoclick_should_not_start_without_input() {
+    let mut autoclicker = Autoclicker::new();
+
+    let started = autoclicker
+        .autoclick(Arc::new(AtomicU64::new(20)), None, None)
+        .await;
+    assert!(!started, "Autoclicker should not start without input");
+    assert!(
+        !autoclicker.running.load(Ordering::SeqCst),
+        "running should be false"
+    );
+    assert!(
+        autoclicker.stopped.load(Ordering::SeqCst),
+        "stopped should be true"
+    );
+}
+
+#[tokio::test(start_paused = true)]
 async fn autoclick_should_not_start_if_already_running() {
-    let mut autoclicker = Autoclicker::new().expect("Failed to create autoclicker");
+    let mut autoclicker = Autoclicker::with_backend(RecordingBackend::new());
 
     autoclicker.running.store(true, Ordering::SeqCst);
     let started = autoclicker
@@ -61,9 +105,34 @@ async fn autoclick_should_not_start_if_already_running() {
     assert!(!started, "Autoclicker should not start if already running");
 }
 
-#[tokio::test]
+#[tokio::test(start_paused = true)]
+async fn autoclick_should_start_once_when_started_together() {
+    let autoclicker = Autoclicker::with_backend(RecordingBackend::new());
+    let delay = Arc::new(AtomicU64::new(20));
+
+    // Both starts have to wait for the input, e.g. while it is reinitialized.
+    let input = autoclicker.input.lock().await;
+    let first = tokio::spawn({
+        let mut autoclicker = autoclicker.clone();
+        let delay = Arc::clone(&delay);
+        async move { autoclicker.autoclick(delay, None, None).await }
+    });
+    let second = tokio::spawn({
+        let mut autoclicker = autoclicker.clone();
+        async move { autoclicker.autoclick(delay, None, None).await }
+    });
+    sleep(Duration::from_millis(10)).await;
+    drop(input);
+
+    let first = first.await.expect("Should not panic");
+    let second = second.await.expect("Should not panic");
+    assert!(first != second, "Exactly one start should succeed");
+    assert!(autoclicker.is_running());
+}
+
+#[tokio::test(start_paused = true)]
 async fn autoclick_should_not_start_when_still_running() {
-    let mut autoclicker = Autoclicker::new().expect("Failed to create autoclicker");
+    let mut autoclicker = Autoclicker::with_backend(RecordingBackend::new());
 
     autoclicker.stopped.store(false, Ordering::SeqCst);
     let started = autoclicker
@@ -71,3 +140,1060 @@ async fn autoclick_should_not_start_when_still_running() {
         .await;
     assert!(!started, "Autoclicker should not start if already running");
 }
+
+#[tokio::test(start_paused = true)]
+async fn autoclick_clicks_with_delay() {
+    let backend = RecordingBackend::new();
+    let mut autoclicker = Autoclicker::with_backend(backend.clone());
+    let delay_ms = Arc::new(AtomicU64::new(50));
+
+    let started = autoclicker.autoclick(delay_ms, None, None).await;
+    assert!(started, "Autoclicker should start");
+
+    sleep(Duration::from_millis(275)).await;
+    autoclicker.running.store(false, Ordering::Release);
+    sleep(Duration::from_millis(50)).await;
+    assert!(autoclicker.is_stopped(), "Autoclicker should have stopped");
+
+    let clicks = backend.clicks(Button::Left);
+    assert_eq!(6, clicks.len(), "Should click every 50 ms");
+    for pair in clicks.windows(2) {
+        assert_eq!(
+            Duration::from_millis(50),
+            pair[1] - pair[0],
+            "Clicks should be 50 ms apart"
+        );
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn autoclick_sends_no_clicks_after_stop() {
+    let backend = RecordingBackend::new();
+    let mut autoclicker = Autoclicker::with_backend(backend.clone());
+
+    autoclicker
+        .autoclick(Arc::new(AtomicU64::new(20)), None, None)
+        .await;
+    sleep(Duration::from_millis(105)).await;
+    autoclicker.running.store(false, Ordering::Release);
+    sleep(Duration::from_millis(50)).await;
+
+    assert_eq!(
+        6,
+        backend.clicks(Button::Left).len(),
+        "Should click every 20 ms before stopping"
+    );
+    sleep(Duration::from_millis(100)).await;
+    assert_eq!(
+        6,
+        backend.clicks(Button::Left).len(),
+        "Should not click after stopping"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn autoclick_waits_for_start_delay() {
+    let backend = RecordingBackend::new();
+    let mut autoclicker = Autoclicker::with_backend(backend.clone());
+    let status = autoclicker.watch_status();
+
+    let start = Instant::now();
+    let started = autoclicker
+        .autoclick(Arc::new(AtomicU64::new(20)), Some(1), None)
+        .await;
+    assert!(started, "Autoclicker should start");
+    sleep(Duration::from_millis(50)).await;
+    assert_eq!(
+        Status::Countdown(1),
+        *status.borrow(),
+        "Should count down the start delay"
+    );
+
+    sleep(Duration::from_millis(1050)).await;
+    assert_eq!(Status::Clicking, *status.borrow());
+    autoclicker.stop();
+    sleep(Duration::from_millis(50)).await;
+    assert_eq!(Status::Idle, *status.borrow());
+
+    let clicks = backend.clicks(Button::Left);
+    assert!(!clicks.is_empty(), "Should have clicked");
+    assert_eq!(
+        Duration::from_secs(1),
+        clicks[0] - start,
+        "First click should happen right after the start delay"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn autoclick_start_delay_can_be_aborted() {
+    let backend = RecordingBackend::new();
+    let mut autoclicker = Autoclicker::with_backend(backend.clone());
+    let status = autoclicker.watch_status();
+    let delay_ms = Arc::new(AtomicU64::new(20));
+
+    autoclicker
+        .autoclick(Arc::clone(&delay_ms), Some(2), Some(1))
+        .await;
+    sleep(Duration::from_millis(50)).await;
+    assert_eq!(Status::Countdown(2), *status.borrow());
+
+    // A second trigger during the countdown stops it.
+    autoclicker
+        .run_hotkey_action(HotkeyAction::Toggle, &HotkeySettings::default(), &delay_ms)
+        .await;
+    sleep(Duration::from_millis(50)).await;
+    assert_eq!(
+        Status::Aborted,
+        *status.borrow(),
+        "Countdown should be aborted"
+    );
+    assert!(autoclicker.is_stopped(), "Autoclicker should have stopped");
+
+    sleep(Duration::from_millis(2000)).await;
+    assert!(
+        backend.clicks(Button::Left).is_empty(),
+        "Should not click after the countdown was aborted"
+    );
+
+    assert!(
+        autoclicker.autoclick(delay_ms, None, None).await,
+        "Should start again after aborting"
+    );
+    autoclicker.stop();
+}
+
+#[tokio::test(start_paused = true)]
+async fn autoclick_stops_after_duration() {
+    let backend = RecordingBackend::new();
+    let mut autoclicker = Autoclicker::with_backend(backend.clone());
+
+    let start = Instant::now();
+    autoclicker
+        .autoclick(Arc::new(AtomicU64::new(30)), None, Some(1))
+        .await;
+    sleep(Duration::from_millis(1100)).await;
+
+    assert!(!autoclicker.is_running(), "Autoclicker should not run");
+    assert!(autoclicker.is_stopped(), "Autoclicker should have stopped");
+    let clicks = backend.clicks(Button::Left);
+    assert_eq!(34, clicks.len(), "Should click every 30 ms for 1 s");
+    assert_eq!(
+        Duration::from_millis(990),
+        clicks[33] - start,
+        "Should not click after the duration"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn stale_duration_timer_does_not_stop_next_run() {
+    let mut autoclicker = Autoclicker::with_backend(RecordingBackend::new());
+    let delay_ms = Arc::new(AtomicU64::new(20));
+
+    autoclicker
+        .autoclick(Arc::clone(&delay_ms), None, Some(1))
+        .await;
+    sleep(Duration::from_millis(100)).await;
+    autoclicker.stop();
+    autoclicker.wait_until_stopped().await;
+
+    assert!(
+        autoclicker
+            .autoclick(Arc::clone(&delay_ms), None, None)
+            .await,
+        "Next run should start"
+    );
+    sleep(Duration::from_millis(1200)).await;
+    assert!(
+        autoclicker.is_running(),
+        "Timer of the previous run should not stop the next run"
+    );
+    autoclicker.stop();
+}
+
+#[tokio::test(start_paused = true)]
+async fn each_run_keeps_its_own_duration() {
+    let mut autoclicker = Autoclicker::with_backend(RecordingBackend::new());
+    let delay_ms = Arc::new(AtomicU64::new(20));
+
+    autoclicker
+        .autoclick(Arc::clone(&delay_ms), None, Some(1))
+        .await;
+    sleep(Duration::from_millis(500)).await;
+    autoclicker.stop();
+    autoclicker.wait_until_stopped().await;
+
+    let start = Instant::now();
+    autoclicker
+        .autoclick(Arc::clone(&delay_ms), None, Some(2))
+        .await;
+    sleep(Duration::from_millis(1000)).await;
+    assert!(
+        autoclicker.is_running(),
+        "Should not stop with the duration of the previous run"
+    );
+    autoclicker.wait_until_stopped().await;
+    let elapsed = start.elapsed();
+    // The run and wait_until_stopped notice the stop within 10 ms each.
+    assert!(
+        (Duration::from_secs(2)..=Duration::from_millis(2020)).contains(&elapsed),
+        "Should stop after its own duration, stopped after {elapsed:?}"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn stale_timers_of_quick_restarts_do_not_stop_last_run() {
+    let mut autoclicker = Autoclicker::with_backend(RecordingBackend::new());
+    let delay_ms = Arc::new(AtomicU64::new(20));
+
+    // Stopping and starting again right away, as fast as a user could with hotkeys.
+    for _ in 0..5 {
+        autoclicker
+            .autoclick(Arc::clone(&delay_ms), None, Some(1))
+            .await;
+        sleep(Duration::from_millis(20)).await;
+        autoclicker.stop();
+        autoclicker.wait_until_stopped().await;
+    }
+    autoclicker
+        .autoclick(Arc::clone(&delay_ms), None, Some(2))
+        .await;
+    sleep(Duration::from_millis(1500)).await;
+    assert!(
+        autoclicker.is_running(),
+        "None of the earlier timers should stop the last run"
+    );
+    autoclicker.stop();
+}
+
+#[tokio::test(start_paused = true)]
+async fn pause_freezes_clicks_and_duration() {
+    let backend = RecordingBackend::new();
+    let mut autoclicker = Autoclicker::with_backend(backend.clone());
+
+    autoclicker
+        .autoclick(Arc::new(AtomicU64::new(20)), None, Some(1))
+        .await;
+    sleep(Duration::from_millis(505)).await;
+    assert!(autoclicker.pause().await, "Should pause the run");
+    assert!(autoclicker.is_paused());
+    assert!(!autoclicker.pause().await, "Should not pause twice");
+    sleep(Duration::from_millis(50)).await;
+    assert_eq!(Status::Paused, *autoclicker.watch_status().borrow());
+
+    assert_eq!(26, backend.clicks(Button::Left).len());
+    sleep(Duration::from_millis(1003)).await;
+    assert_eq!(
+        26,
+        backend.clicks(Button::Left).len(),
+        "Should not click while paused"
+    );
+    assert!(
+        autoclicker.is_running(),
+        "Time spent paused should not count towards the duration"
+    );
+
+    assert!(autoclicker.resume(), "Should resume the run");
+    sleep(Duration::from_millis(50)).await;
+    assert_eq!(Status::Clicking, *autoclicker.watch_status().borrow());
+    assert_eq!(
+        29,
+        backend.clicks(Button::Left).len(),
+        "Should click again after resuming"
+    );
+
+    // The duration counts in 10 ms steps, so the 5 ms before the pause are not counted.
+    autoclicker.wait_until_stopped().await;
+    assert_eq!(
+        51,
+        backend.clicks(Button::Left).len(),
+        "Should finish the remaining duration after resuming"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn stopping_while_paused_sends_no_input() {
+    let backend = RecordingBackend::new();
+    let mut autoclicker = Autoclicker::with_backend(backend.clone());
+
+    autoclicker
+        .autoclick(Arc::new(AtomicU64::new(20)), None, None)
+        .await;
+    sleep(Duration::from_millis(105)).await;
+    autoclicker.toggle_pause().await;
+
+    autoclicker.stop();
+    autoclicker.wait_until_stopped().await;
+    assert_eq!(
+        6,
+        backend.clicks(Button::Left).len(),
+        "Should not click after a stop while paused"
+    );
+    assert!(!autoclicker.is_paused(), "Stopping should end the pause");
+    assert_eq!(Status::Idle, *autoclicker.watch_status().borrow());
+}
+
+#[tokio::test(start_paused = true)]
+async fn pause_needs_a_running_run() {
+    let mut autoclicker = Autoclicker::with_backend(RecordingBackend::new());
+    assert!(!autoclicker.pause().await, "Nothing to pause");
+
+    autoclicker
+        .autoclick(Arc::new(AtomicU64::new(20)), Some(1), None)
+        .await;
+    sleep(Duration::from_millis(50)).await;
+    assert!(
+        !autoclicker.pause().await,
+        "Should not pause during the start delay"
+    );
+    autoclicker.stop();
+}
+
+#[tokio::test(start_paused = true)]
+async fn run_hotkey_action_controls_autoclicker() {
+    let mut autoclicker = Autoclicker::with_backend(RecordingBackend::new());
+    let delay_ms = Arc::new(AtomicU64::new(20));
+
+    assert!(
+        autoclicker
+            .run_hotkey_action(HotkeyAction::Start, &HotkeySettings::default(), &delay_ms)
+            .await
+    );
+    assert!(
+        autoclicker.is_running(),
+        "Start should start the autoclicker"
+    );
+    assert!(
+        autoclicker
+            .run_hotkey_action(HotkeyAction::Start, &HotkeySettings::default(), &delay_ms)
+            .await
+    );
+    assert!(
+        autoclicker.is_running(),
+        "Start should not stop a running autoclicker"
+    );
+
+    assert!(
+        autoclicker
+            .run_hotkey_action(HotkeyAction::Stop, &HotkeySettings::default(), &delay_ms)
+            .await
+    );
+    assert!(
+        !autoclicker.is_running(),
+        "Stop should stop the autoclicker"
+    );
+    sleep(Duration::from_millis(50)).await;
+
+    assert!(
+        autoclicker
+            .run_hotkey_action(HotkeyAction::Toggle, &HotkeySettings::default(), &delay_ms)
+            .await
+    );
+    assert!(
+        autoclicker.is_running(),
+        "Toggle should start a stopped autoclicker"
+    );
+    assert!(
+        autoclicker
+            .run_hotkey_action(HotkeyAction::Toggle, &HotkeySettings::default(), &delay_ms)
+            .await
+    );
+    assert!(
+        !autoclicker.is_running(),
+        "Toggle should stop a running autoclicker"
+    );
+
+    assert!(
+        !autoclicker
+            .run_hotkey_action(
+                HotkeyAction::RaiseDelay,
+                &HotkeySettings::default(),
+                &delay_ms
+            )
+            .await,
+        "Changing the delay is not handled by the autoclicker"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn hold_mode_clicks_while_hotkey_is_held() {
+    let mut autoclicker = Autoclicker::with_backend(RecordingBackend::new());
+    let delay_ms = Arc::new(AtomicU64::new(20));
+    let pressed = HotkeyEvent::Pressed(HotkeyAction::Toggle);
+    let released = HotkeyEvent::Released(HotkeyAction::Toggle);
+    let hold = HotkeySettings {
+        mode: HotkeyMode::Hold,
+        ..Default::default()
+    };
+
+    let unhandled = autoclicker
+        .handle_hotkey_event(pressed, &hold, &delay_ms)
+        .await;
+    assert_eq!(
+        None, unhandled,
+        "Toggle should be handled by the autoclicker"
+    );
+    assert!(
+        autoclicker.is_running(),
+        "Pressing should start the autoclicker"
+    );
+
+    // Pressing again while held must not stop it, only releasing does.
+    autoclicker
+        .handle_hotkey_event(pressed, &hold, &delay_ms)
+        .await;
+    assert!(
+        autoclicker.is_running(),
+        "Repeated press should keep it running"
+    );
+
+    autoclicker
+        .handle_hotkey_event(released, &hold, &delay_ms)
+        .await;
+    assert!(
+        !autoclicker.is_running(),
+        "Releasing should stop the autoclicker"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn toggle_mode_ignores_release() {
+    let mut autoclicker = Autoclicker::with_backend(RecordingBackend::new());
+    let delay_ms = Arc::new(AtomicU64::new(20));
+
+    autoclicker
+        .handle_hotkey_event(
+            HotkeyEvent::Pressed(HotkeyAction::Toggle),
+            &HotkeySettings::default(),
+            &delay_ms,
+        )
+        .await;
+    autoclicker
+        .handle_hotkey_event(
+            HotkeyEvent::Released(HotkeyAction::Toggle),
+            &HotkeySettings::default(),
+            &delay_ms,
+        )
+        .await;
+    assert!(
+        autoclicker.is_running(),
+        "Releasing should not stop the autoclicker"
+    );
+
+    let unhandled = autoclicker
+        .handle_hotkey_event(
+            HotkeyEvent::Pressed(HotkeyAction::ToggleWindow),
+            &HotkeySettings::default(),
+            &delay_ms,
+        )
+        .await;
+    assert_eq!(
+        Some(HotkeyAction::ToggleWindow),
+        unhandled,
+        "Window actions should be passed on"
+    );
+    autoclicker.running.store(false, Ordering::Release);
+}
+
+#[tokio::test(start_paused = true)]
+async fn hotkey_runs_use_settings_duration() {
+    let mut autoclicker = Autoclicker::with_backend(RecordingBackend::new());
+    let delay_ms = Arc::new(AtomicU64::new(20));
+    let settings = HotkeySettings {
+        hotkey: RunTiming {
+            start_delay: None,
+            duration: Some(1),
+        },
+        ..Default::default()
+    };
+
+    autoclicker
+        .handle_hotkey_event(
+            HotkeyEvent::Pressed(HotkeyAction::Start),
+            &settings,
+            &delay_ms,
+        )
+        .await;
+    assert!(
+        autoclicker.is_running(),
+        "Start should start the autoclicker"
+    );
+
+    sleep(Duration::from_millis(1100)).await;
+    assert!(
+        !autoclicker.is_running(),
+        "Hotkey run should stop after the duration"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn autoclick_never_clicks_its_trigger() {
+    let backend = RecordingBackend::new();
+    let mut autoclicker = Autoclicker::with_backend(backend.clone());
+    let delay_ms = Arc::new(AtomicU64::new(20));
+
+    autoclicker.exclude_button(Some(CLICK_BUTTON));
+    assert!(
+        !autoclicker
+            .autoclick(Arc::clone(&delay_ms), None, None)
+            .await,
+        "Should not click the button that triggers it"
+    );
+    assert!(backend.clicks(CLICK_BUTTON).is_empty());
+
+    autoclicker.exclude_button(Some(Button::Back));
+    assert!(autoclicker.is_excluded(Button::Back));
+    assert!(
+        autoclicker.autoclick(delay_ms, None, None).await,
+        "Should click when another button is the trigger"
+    );
+    autoclicker.stop();
+}
+
+fn click_macro(button: Button, delay_ms: u64) -> Arc<Macro> {
+    Arc::new(Macro {
+        events: vec![
+            MacroEvent {
+                delay_ms,
+                action: MacroAction::Move { dx: 5, dy: 0 },
+            },
+            MacroEvent {
+                delay_ms,
+                action: MacroAction::Button {
+                    button,
+                    direction: Direction::Click,
+                },
+            },
+        ],
+    })
+}
+
+#[tokio::test(start_paused = true)]
+async fn play_macro_repeats_for_loops() {
+    let backend = RecordingBackend::new();
+    let mut autoclicker = Autoclicker::with_backend(backend.clone());
+
+    let started = autoclicker
+        .play_macro(click_macro(Button::Right, 10), 100, 2, None, None)
+        .await;
+    assert!(started, "Macro should start");
+    sleep(Duration::from_millis(150)).await;
+
+    assert!(
+        autoclicker.is_stopped(),
+        "Macro should stop after the loops"
+    );
+    assert_eq!(
+        vec![
+            InputEvent::Move(5, 0),
+            InputEvent::Button(Button::Right, Direction::Click),
+            InputEvent::Move(5, 0),
+            InputEvent::Button(Button::Right, Direction::Click),
+        ],
+        backend.events()
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn play_macro_scales_delays_with_speed() {
+    let backend = RecordingBackend::new();
+    let mut autoclicker = Autoclicker::with_backend(backend.clone());
+
+    let start = Instant::now();
+    autoclicker
+        .play_macro(click_macro(Button::Right, 100), 200, 1, None, None)
+        .await;
+    sleep(Duration::from_millis(200)).await;
+
+    let clicks = backend.clicks(Button::Right);
+    assert_eq!(1, clicks.len(), "Should play the macro once");
+    assert_eq!(
+        Duration::from_millis(100),
+        clicks[0] - start,
+        "Double speed should halve the delays"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn play_macro_loops_until_stopped() {
+    let backend = RecordingBackend::new();
+    let mut autoclicker = Autoclicker::with_backend(backend.clone());
+
+    autoclicker
+        .play_macro(click_macro(Button::Right, 10), 100, 0, None, None)
+        .await;
+    sleep(Duration::from_millis(155)).await;
+    assert!(autoclicker.is_running(), "Macro should loop forever");
+    autoclicker.stop();
+    sleep(Duration::from_millis(50)).await;
+
+    assert!(autoclicker.is_stopped(), "Macro should have stopped");
+    assert_eq!(
+        7,
+        backend.clicks(Button::Right).len(),
+        "Macro should have looped every 20 ms"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn play_macro_with_huge_delay_waits_until_stopped() {
+    let backend = RecordingBackend::new();
+    let mut autoclicker = Autoclicker::with_backend(backend.clone());
+
+    autoclicker
+        .play_macro(click_macro(Button::Right, u64::MAX), 10, 1, None, None)
+        .await;
+    sleep(Duration::from_millis(100)).await;
+    assert!(autoclicker.is_running(), "Macro should still be waiting");
+    autoclicker.stop();
+    sleep(Duration::from_millis(50)).await;
+
+    assert!(autoclicker.is_stopped(), "Macro should have stopped");
+    assert!(backend.events().is_empty(), "Macro should not have acted");
+}
+
+#[tokio::test(start_paused = true)]
+async fn play_macro_skips_excluded_button_and_releases_held_input() {
+    let backend = RecordingBackend::new();
+    let mut autoclicker = Autoclicker::with_backend(backend.clone());
+    autoclicker.exclude_button(Some(Button::Back));
+    let recorded = Arc::new(Macro {
+        events: vec![
+            MacroEvent {
+                delay_ms: 0,
+                action: MacroAction::Button {
+                    button: Button::Back,
+                    direction: Direction::Click,
+                },
+            },
+            MacroEvent {
+                delay_ms: 0,
+                action: MacroAction::Key {
+                    code: 30,
+                    direction: Direction::Press,
+                },
+            },
+        ],
+    });
+
+    autoclicker.play_macro(recorded, 100, 1, None, None).await;
+    sleep(Duration::from_millis(50)).await;
+
+    assert_eq!(
+        vec![
+            InputEvent::Key(30, Direction::Press),
+            InputEvent::Key(30, Direction::Release),
+        ],
+        backend.events(),
+        "Excluded button should be skipped and the held key released"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn shutdown_releases_held_input_of_paused_run() {
+    let backend = RecordingBackend::new();
+    let mut autoclicker = Autoclicker::with_backend(backend.clone());
+    let drag = Arc::new(Macro {
+        events: vec![
+            MacroEvent {
+                delay_ms: 0,
+                action: MacroAction::Button {
+                    button: Button::Left,
+                    direction: Direction::Press,
+                },
+            },
+            MacroEvent {
+                delay_ms: 5000,
+                action: MacroAction::Button {
+                    button: Button::Left,
+                    direction: Direction::Release,
+                },
+            },
+        ],
+    });
+
+    autoclicker.play_macro(drag, 100, 0, None, None).await;
+    sleep(Duration::from_millis(50)).await;
+    assert!(autoclicker.pause().await, "Should pause the macro");
+
+    let start = Instant::now();
+    autoclicker.shutdown(Duration::from_secs(2)).await;
+    assert!(
+        start.elapsed() < Duration::from_secs(1),
+        "Should not wait for the macro"
+    );
+    assert_eq!(
+        vec![
+            InputEvent::Button(Button::Left, Direction::Press),
+            InputEvent::Button(Button::Left, Direction::Release),
+        ],
+        backend.events(),
+        "The held button should be released"
+    );
+    assert!(autoclicker.is_stopped());
+    assert!(!autoclicker.is_ready().await, "The input should be dropped");
+}
+
+#[tokio::test(start_paused = true)]
+async fn play_macro_should_not_start_when_empty() {
+    let mut autoclicker = Autoclicker::with_backend(RecordingBackend::new());
+    let started = autoclicker
+        .play_macro(Arc::new(Macro::default()), 100, 1, None, None)
+        .await;
+    assert!(!started, "Empty macro should not start");
+    assert!(autoclicker.is_stopped());
+}
+
+#[tokio::test(start_paused = true)]
+async fn panicking_run_can_be_followed_by_another() {
+    let backend = RecordingBackend::new();
+    let mut autoclicker = Autoclicker::with_backend(backend.clone());
+
+    let started = autoclicker
+        .start(None, None, Status::Scripting, |_, _| async {
+            panic!("run failed");
+        })
+        .await;
+    assert!(started, "Run should start");
+    sleep(Duration::from_millis(10)).await;
+
+    assert!(autoclicker.is_stopped(), "Panicking run should be stopped");
+    assert!(!autoclicker.is_running());
+    assert_eq!(
+        Status::Failed("the run panicked".to_string()),
+        *autoclicker.status.borrow()
+    );
+    assert!(
+        autoclicker
+            .autoclick(Arc::new(AtomicU64::new(20)), None, None)
+            .await,
+        "Should start again after the panic"
+    );
+    autoclicker.stop();
+}
+
+#[tokio::test(start_paused = true)]
+async fn keep_alive_acts_only_while_user_is_inactive() {
+    let backend = RecordingBackend::new();
+    let mut autoclicker = Autoclicker::with_backend(backend.clone());
+    let last_input = Arc::new(std::sync::Mutex::new(Instant::now()));
+    let monitor = IdleMonitor::Evdev {
+        last_input: Arc::clone(&last_input),
+        _reader: DeviceReader::spawn(Vec::new(), |_| true),
+    };
+
+    let started = autoclicker
+        .keep_alive(monitor, Duration::from_millis(300), KeepaliveAction::Nudge)
+        .await;
+    assert!(started, "Keep-alive should start");
+
+    // The user keeps working.
+    for _ in 0..16 {
+        *last_input.lock().unwrap() = Instant::now();
+        sleep(Duration::from_millis(50)).await;
+    }
+    assert!(
+        backend.events().is_empty(),
+        "Should not act while the user is active"
+    );
+    assert_eq!(Status::KeepingAlive, *autoclicker.watch_status().borrow());
+
+    sleep(Duration::from_millis(600)).await;
+    // The user was last active after 750 ms, the poll after 1250 ms notices it.
+    assert_eq!(
+        vec![InputEvent::Move(1, 0), InputEvent::Move(-1, 0)],
+        backend.events(),
+        "Should nudge the pointer once the user is inactive"
+    );
+
+    autoclicker.stop();
+    autoclicker.wait_until_stopped().await;
+    assert_eq!(Status::Idle, *autoclicker.watch_status().borrow());
+}
+
+#[tokio::test(start_paused = true)]
+async fn failsafe_corner_stops_clicking() {
+    let backend = RecordingBackend::new();
+    backend.set_location(500, 500);
+    let mut autoclicker = Autoclicker::with_backend(backend.clone());
+    autoclicker.failsafe().set_corner(FailsafeCorner::TopLeft);
+
+    autoclicker
+        .autoclick(Arc::new(AtomicU64::new(20)), None, None)
+        .await;
+    sleep(Duration::from_millis(105)).await;
+    assert!(autoclicker.is_running(), "Should click outside the corner");
+
+    backend.set_location(0, 0);
+    sleep(Duration::from_millis(50)).await;
+    assert!(autoclicker.is_stopped(), "Corner should stop the run");
+    assert_eq!(
+        6,
+        backend.clicks(Button::Left).len(),
+        "Should not click in the corner"
+    );
+    let Status::Failed(reason) = autoclicker.watch_status().borrow().clone() else {
+        panic!("Status should show the reason");
+    };
+    assert!(reason.contains("failsafe"), "Got: {reason}");
+}
+
+#[tokio::test]
+async fn failsafe_waits_for_rhai_script_to_end() {
+    let mut autoclicker = Autoclicker::with_backend(RecordingBackend::new());
+    let script =
+        Program::parse(Language::Rhai, "loop { sleep(1000); }").expect("Should parse script");
+
+    autoclicker.run_script(Arc::new(script), None, None).await;
+    sleep(Duration::from_millis(50)).await;
+    assert!(autoclicker.is_running(), "Script should be running");
+
+    for _ in 0..ESCAPE_PRESSES {
+        autoclicker.failsafe().escape_pressed();
+    }
+    autoclicker.wait_until_stopped().await;
+    assert_eq!(
+        1,
+        Arc::strong_count(&autoclicker.input),
+        "The script should have ended before the run was stopped"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn failsafe_escape_stops_script() {
+    let backend = RecordingBackend::new();
+    let mut autoclicker = Autoclicker::with_backend(backend.clone());
+    let script = Program::parse(Language::Actions, "repeat 1000 {\nclick\nwait 30ms\n}")
+        .expect("Should parse script");
+
+    // Presses before the run don't count.
+    for _ in 0..ESCAPE_PRESSES {
+        autoclicker.failsafe().escape_pressed();
+    }
+    autoclicker.run_script(Arc::new(script), None, None).await;
+    sleep(Duration::from_millis(95)).await;
+    assert!(autoclicker.is_running(), "Earlier presses should be reset");
+    assert_eq!(
+        4,
+        backend.clicks(Button::Left).len(),
+        "Should click every 30 ms"
+    );
+
+    for _ in 0..ESCAPE_PRESSES {
+        autoclicker.failsafe().escape_pressed();
+    }
+    sleep(Duration::from_millis(50)).await;
+    assert!(autoclicker.is_stopped(), "Escape should stop the script");
+    assert_eq!(
+        4,
+        backend.clicks(Button::Left).len(),
+        "Should not click after Escape"
+    );
+    assert!(matches!(
+        *autoclicker.watch_status().borrow(),
+        Status::Failed(_)
+    ));
+}
+
+#[tokio::test(start_paused = true)]
+async fn session_click_limit_stops_every_run() {
+    let backend = RecordingBackend::new();
+    let mut autoclicker = Autoclicker::with_backend(backend.clone());
+    autoclicker.set_limits(Limits {
+        max_session_clicks: 3,
+        ..Default::default()
+    });
+
+    autoclicker
+        .autoclick(Arc::new(AtomicU64::new(20)), None, None)
+        .await;
+    sleep(Duration::from_millis(200)).await;
+    assert!(autoclicker.is_stopped(), "Limit should stop the run");
+    assert_eq!(3, backend.clicks(Button::Left).len());
+    let Status::Failed(reason) = autoclicker.watch_status().borrow().clone() else {
+        panic!("Status should show the reason");
+    };
+    assert!(reason.contains("clicks per session"), "Got: {reason}");
+
+    autoclicker
+        .play_macro(click_macro(Button::Left, 10), 100, 0, None, None)
+        .await;
+    sleep(Duration::from_millis(100)).await;
+    assert!(
+        autoclicker.is_stopped(),
+        "The limit is for the whole session"
+    );
+    assert_eq!(3, backend.clicks(Button::Left).len());
+}
+
+#[tokio::test(start_paused = true)]
+async fn user_limits_cannot_exceed_system_limits() {
+    let backend = RecordingBackend::new();
+    let mut autoclicker = Autoclicker::with_backend(backend.clone());
+    autoclicker.set_system_limits(Limits {
+        max_session_clicks: 3,
+        ..Default::default()
+    });
+    autoclicker.set_limits(Limits {
+        max_session_clicks: 5000,
+        ..Default::default()
+    });
+
+    autoclicker
+        .autoclick(Arc::new(AtomicU64::new(20)), None, None)
+        .await;
+    sleep(Duration::from_millis(200)).await;
+    assert!(
+        autoclicker.is_stopped(),
+        "The limit of the administrator should stop the run"
+    );
+    assert_eq!(3, backend.clicks(Button::Left).len());
+}
+
+#[test]
+fn limits_within_keeps_the_stricter_value() {
+    let system = Limits {
+        max_clicks_per_second: 10,
+        max_run_minutes: 60,
+        max_session_clicks: 0,
+    };
+    let user = Limits {
+        max_clicks_per_second: 0,
+        max_run_minutes: 30,
+        max_session_clicks: 100,
+    };
+    assert_eq!(
+        Limits {
+            max_clicks_per_second: 10,
+            max_run_minutes: 30,
+            max_session_clicks: 100,
+        },
+        user.within(system),
+        "Turning a limit off should not remove the one of the administrator"
+    );
+
+    let user = Limits {
+        max_clicks_per_second: 1000,
+        max_run_minutes: 1440,
+        max_session_clicks: 0,
+    };
+    assert_eq!(
+        system,
+        user.within(system),
+        "Higher limits should not exceed the ones of the administrator"
+    );
+}
+
+#[test]
+fn system_limits_from_path() {
+    let tmp_dir = tempfile::tempdir().expect("Should create temporary directory");
+    let path = tmp_dir.path().join("limits.json");
+    assert_eq!(
+        Limits::default(),
+        Limits::from_system_path(&path).expect("Missing file should not be an error"),
+        "Without the file there should be no limits"
+    );
+
+    std::fs::write(&path, r#"{"max_clicks_per_second": 10}"#).expect("Should write file");
+    assert_eq!(
+        Limits {
+            max_clicks_per_second: 10,
+            ..Default::default()
+        },
+        Limits::from_system_path(&path).expect("Should load limits")
+    );
+
+    std::fs::write(&path, "max_clicks_per_second = 10").expect("Should write file");
+    assert!(
+        Limits::from_system_path(&path).is_err(),
+        "Invalid file should be an error"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn autoclick_should_not_start_when_runs_are_refused() {
+    let backend = RecordingBackend::new();
+    let mut autoclicker = Autoclicker::with_backend(backend.clone());
+    autoclicker.refuse_runs("bad limits".to_string());
+
+    let started = autoclicker
+        .autoclick(Arc::new(AtomicU64::new(20)), None, None)
+        .await;
+    assert!(!started, "Autoclicker should refuse to start");
+    assert_eq!(
+        Status::Failed("bad limits".to_string()),
+        *autoclicker.watch_status().borrow()
+    );
+    sleep(Duration::from_millis(100)).await;
+    assert!(backend.clicks(Button::Left).is_empty(), "Should not click");
+}
+
+#[tokio::test(start_paused = true)]
+async fn clicks_per_second_limit_slows_autoclick() {
+    let backend = RecordingBackend::new();
+    let mut autoclicker = Autoclicker::with_backend(backend.clone());
+    autoclicker.set_limits(Limits {
+        max_clicks_per_second: 10,
+        ..Default::default()
+    });
+
+    autoclicker
+        .autoclick(Arc::new(AtomicU64::new(20)), None, None)
+        .await;
+    sleep(Duration::from_millis(550)).await;
+    assert!(autoclicker.is_running(), "Should click slower, not stop");
+    assert_eq!(
+        6,
+        backend.clicks(Button::Left).len(),
+        "Should click every 100 ms instead of every 20 ms"
+    );
+    autoclicker.stop();
+}
+
+#[tokio::test(start_paused = true)]
+async fn clicks_per_second_limit_stops_macro() {
+    let backend = RecordingBackend::new();
+    let mut autoclicker = Autoclicker::with_backend(backend.clone());
+    autoclicker.set_limits(Limits {
+        max_clicks_per_second: 5,
+        ..Default::default()
+    });
+
+    autoclicker
+        .play_macro(click_macro(Button::Left, 10), 100, 0, None, None)
+        .await;
+    sleep(Duration::from_millis(200)).await;
+    assert!(autoclicker.is_stopped(), "Limit should stop the macro");
+    assert_eq!(5, backend.clicks(Button::Left).len());
+    let Status::Failed(reason) = autoclicker.watch_status().borrow().clone() else {
+        panic!("Status should show the reason");
+    };
+    assert!(reason.contains("clicks per second"), "Got: {reason}");
+}
+
+#[test]
+fn run_time_limit_wins_over_longer_duration() {
+    let limits = Limits {
+        max_run_minutes: 2,
+        ..Default::default()
+    };
+    assert_eq!(
+        Some((Duration::from_secs(60), None)),
+        run_timer(Some(60), &limits)
+    );
+    let (after, reason) = run_timer(Some(600), &limits).expect("Should stop the run");
+    assert_eq!(Duration::from_secs(120), after);
+    assert!(reason.is_some_and(|reason| reason.contains("2 min")));
+    assert!(
+        run_timer(None, &limits).is_some(),
+        "Runs without duration are limited too"
+    );
+    assert_eq!(None, run_timer(None, &Limits::default()));
+    assert_eq!(
+        100,
+        Limits {
+            max_clicks_per_second: 10,
+            ..Default::default()
+        }
+        .min_delay_ms()
+    );
+}
@@ -0,0 +1,301 @@
+use crate::failsafe::FailsafeCorner;
+use crate::hotkey::{HotkeyBackendKind, HotkeyMode, HotkeyTiming, MouseTrigger};
+use crate::idle::{IdleBackendKind, KeepaliveAction};
+use crate::input::BackendKind;
+use crate::state::State;
+use clap::{Args, Parser, builder::BoolishValueParser};
+use std::path::PathBuf;
+
+#[cfg(test)]
+mod test;
+
+/// GUI based auto-clicker for Linux (X11/Wayland)
+#[derive(Parser, Debug)]
+#[command(version, about)]
+pub struct Cli {
+    /// Run the action script without opening the window and exit once it is done
+    #[arg(long, value_name = "PATH")]
+    pub run_script: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub overrides: Overrides,
+}
+
+/// Settings that override the saved State for this session.
+/// Each setting can be given as CLI flag or environment variable, flags take precedence.
+#[derive(Args, Debug, Default, Clone, PartialEq)]
+pub struct Overrides {
+    /// Delay between clicks in milliseconds
+    #[arg(long, env = "TURBO_CLICKER_DELAY", value_parser = clap::value_parser!(u64).range(20..=1000))]
+    pub delay: Option<u64>,
+
+    /// Delay in seconds before starting to click, implies --use-start-delay=true unless set explicitly
+    #[arg(long, env = "TURBO_CLICKER_START_DELAY", value_parser = clap::value_parser!(u64).range(1..=60))]
+    pub start_delay: Option<u64>,
+
+    /// Time in seconds after which to stop clicking, implies --use-duration=true unless set explicitly
+    #[arg(long, env = "TURBO_CLICKER_DURATION", value_parser = clap::value_parser!(u64).range(1..=60))]
+    pub duration: Option<u64>,
+
+    /// Wait for the start delay before clicking
+    #[arg(long, env = "TURBO_CLICKER_USE_START_DELAY", value_name = "BOOL", value_parser = BoolishValueParser::new())]
+    pub use_start_delay: Option<bool>,
+
+    /// Stop clicking after the duration
+    #[arg(long, env = "TURBO_CLICKER_USE_DURATION", value_name = "BOOL", value_parser = BoolishValueParser::new())]
+    pub use_duration: Option<bool>,
+
+    /// Use the dark color scheme
+    #[arg(long, env = "TURBO_CLICKER_DARK_MODE", value_name = "BOOL", value_parser = BoolishValueParser::new())]
+    pub dark_mode: Option<bool>,
+
+    /// Backend used for virtual input
+    #[arg(long, env = "TURBO_CLICKER_INPUT_BACKEND")]
+    pub input_backend: Option<BackendKind>,
+
+    /// Whether the toggle shortcut starts/stops clicking or clicks while held
+    #[arg(long, env = "TURBO_CLICKER_HOTKEY_MODE")]
+    pub hotkey_mode: Option<HotkeyMode>,
+
+    /// Backend used for global hotkeys
+    #[arg(long, env = "TURBO_CLICKER_HOTKEY_BACKEND")]
+    pub hotkey_backend: Option<HotkeyBackendKind>,
+
+    /// Key combination of the start/stop hotkey for the X11 and evdev backends, e.g. "CTRL+SHIFT+F12"
+    #[arg(long, env = "TURBO_CLICKER_HOTKEY_TRIGGER", value_name = "TRIGGER")]
+    pub hotkey_trigger: Option<String>,
+
+    /// Whether runs started by the start or toggle shortcut use the start delay and duration
+    #[arg(long, env = "TURBO_CLICKER_HOTKEY_TIMING")]
+    pub hotkey_timing: Option<HotkeyTiming>,
+
+    /// Whether runs started by holding the toggle shortcut use the start delay and duration
+    #[arg(long, env = "TURBO_CLICKER_HOLD_TIMING")]
+    pub hold_timing: Option<HotkeyTiming>,
+
+    /// Mouse button that starts/stops clicking like the toggle shortcut
+    #[arg(long, env = "TURBO_CLICKER_MOUSE_TRIGGER", value_name = "BUTTON")]
+    pub mouse_trigger: Option<MouseTrigger>,
+
+    /// Whether runs started by the mouse button use the start delay and duration
+    #[arg(long, env = "TURBO_CLICKER_MOUSE_TIMING")]
+    pub mouse_timing: Option<HotkeyTiming>,
+
+    /// Replay speed of macros in percent
+    #[arg(long, env = "TURBO_CLICKER_MACRO_SPEED", value_parser = clap::value_parser!(u64).range(10..=1000))]
+    pub macro_speed: Option<u64>,
+
+    /// How often a macro is replayed, 0 repeats it until stopped
+    #[arg(long, env = "TURBO_CLICKER_MACRO_LOOPS", value_parser = clap::value_parser!(u64).range(0..=1000))]
+    pub macro_loops: Option<u64>,
+
+    /// Minutes of inactivity after which the keep-alive mode acts
+    #[arg(long, env = "TURBO_CLICKER_KEEPALIVE_MINUTES", value_parser = clap::value_parser!(u64).range(1..=1440))]
+    pub keepalive_minutes: Option<u64>,
+
+    /// What the keep-alive mode does once the user is inactive
+    #[arg(long, env = "TURBO_CLICKER_KEEPALIVE_ACTION")]
+    pub keepalive_action: Option<KeepaliveAction>,
+
+    /// How the keep-alive mode detects that the user is inactive
+    #[arg(long, env = "TURBO_CLICKER_IDLE_BACKEND")]
+    pub idle_backend: Option<IdleBackendKind>,
+
+    /// The screen corner that stops the autoclicker when the pointer is moved into it
+    #[arg(long, env = "TURBO_CLICKER_FAILSAFE_CORNER")]
+    pub failsafe_corner: Option<FailsafeCorner>,
+
+    /// Highest number of clicks per second of any run, 0 for no limit, a limit of the administrator still applies
+    #[arg(long, env = "TURBO_CLICKER_MAX_CLICKS_PER_SECOND", value_parser = clap::value_parser!(u64).range(0..=1000))]
+    pub max_clicks_per_second: Option<u64>,
+
+    /// Longest a run may go on in minutes, 0 for no limit, a limit of the administrator still applies
+    #[arg(long, env = "TURBO_CLICKER_MAX_RUN_MINUTES", value_parser = clap::value_parser!(u64).range(0..=1440))]
+    pub max_run_minutes: Option<u64>,
+
+    /// Most clicks until the app is restarted, 0 for no limit, a limit of the administrator still applies
+    #[arg(long, env = "TURBO_CLICKER_MAX_SESSION_CLICKS", value_parser = clap::value_parser!(u64).range(0..=1_000_000))]
+    pub max_session_clicks: Option<u64>,
+
+    /// Save overridden settings back to the state file, by default the saved settings are kept
+    #[arg(long, env = "TURBO_CLICKER_SAVE_OVERRIDES")]
+    pub save_overrides: bool,
+}
+
+impl Overrides {
+    /// Check if no setting is overridden.
+    pub fn is_empty(&self) -> bool {
+        self.delay.is_none()
+            && self.start_delay.is_none()
+            && self.duration.is_none()
+            && self.use_start_delay.is_none()
+            && self.use_duration.is_none()
+            && self.dark_mode.is_none()
+            && self.input_backend.is_none()
+            && self.hotkey_mode.is_none()
+            && self.hotkey_backend.is_none()
+            && self.hotkey_trigger.is_none()
+            && self.hotkey_timing.is_none()
+            && self.hold_timing.is_none()
+            && self.mouse_trigger.is_none()
+            && self.mouse_timing.is_none()
+            && self.macro_speed.is_none()
+            && self.macro_loops.is_none()
+            && self.keepalive_minutes.is_none()
+            && self.keepalive_action.is_none()
+            && self.idle_backend.is_none()
+            && self.failsafe_corner.is_none()
+            && self.max_clicks_per_second.is_none()
+            && self.max_run_minutes.is_none()
+            && self.max_session_clicks.is_none()
+    }
+
+    /// Apply the overrides to the given state.
+    pub fn apply(&self, state: &mut State) {
+        if let Some(delay) = self.delay {
+            state.delay = delay;
+        }
+        if let Some(start_delay) = self.start_delay {
+            state.start_delay = start_delay;
+            state.use_start_delay = true;
+        }
+        if let Some(duration) = self.duration {
+            state.duration = duration;
+            state.use_duration = true;
+        }
+        if let Some(use_start_delay) = self.use_start_delay {
+            state.use_start_delay = use_start_delay;
+        }
+        if let Some(use_duration) = self.use_duration {
+            state.use_duration = use_duration;
+        }
+        if let Some(dark_mode) = self.dark_mode {
+            state.dark_mode = dark_mode;
+        }
+        if let Some(input_backend) = self.input_backend {
+            state.input_backend = input_backend;
+        }
+        if let Some(hotkey_mode) = self.hotkey_mode {
+            state.hotkey_mode = hotkey_mode;
+        }
+        if let Some(hotkey_backend) = self.hotkey_backend {
+            state.hotkey_backend = hotkey_backend;
+        }
+        if let Some(hotkey_trigger) = &self.hotkey_trigger {
+            state.hotkey_trigger = hotkey_trigger.clone();
+        }
+        if let Some(hotkey_timing) = self.hotkey_timing {
+            state.hotkey_timing = hotkey_timing;
+        }
+        if let Some(hold_timing) = self.hold_timing {
+            state.hold_timing = hold_timing;
+        }
+        if let Some(mouse_trigger) = self.mouse_trigger {
+            state.mouse_trigger = mouse_trigger;
+        }
+        if let Some(mouse_timing) = self.mouse_timing {
+            state.mouse_timing = mouse_timing;
+        }
+        if let Some(macro_speed) = self.macro_speed {
+            state.macro_speed = macro_speed;
+        }
+        if let Some(macro_loops) = self.macro_loops {
+            state.macro_loops = macro_loops;
+        }
+        if let Some(keepalive_minutes) = self.keepalive_minutes {
+            state.keepalive_minutes = keepalive_minutes;
+        }
+        if let Some(keepalive_action) = self.keepalive_action {
+            state.keepalive_action = keepalive_action;
+        }
+        if let Some(idle_backend) = self.idle_backend {
+            state.idle_backend = idle_backend;
+        }
+        if let Some(failsafe_corner) = self.failsafe_corner {
+            state.failsafe_corner = failsafe_corner;
+        }
+        if let Some(max_clicks_per_second) = self.max_clicks_per_second {
+            state.max_clicks_per_second = max_clicks_per_second;
+        }
+        if let Some(max_run_minutes) = self.max_run_minutes {
+            state.max_run_minutes = max_run_minutes;
+        }
+        if let Some(max_session_clicks) = self.max_session_clicks {
+            state.max_session_clicks = max_session_clicks;
+        }
+    }
+
+    /// Reset all overridden settings in state to the values from saved.
+    /// Used to keep the overrides out of the state file.
+    pub fn revert(&self, state: &mut State, saved: &State) {
+        if self.delay.is_some() {
+            state.delay = saved.delay;
+        }
+        if self.start_delay.is_some() {
+            state.start_delay = saved.start_delay;
+        }
+        if self.duration.is_some() {
+            state.duration = saved.duration;
+        }
+        if self.start_delay.is_some() || self.use_start_delay.is_some() {
+            state.use_start_delay = saved.use_start_delay;
+        }
+        if self.duration.is_some() || self.use_duration.is_some() {
+            state.use_duration = saved.use_duration;
+        }
+        if self.dark_mode.is_some() {
+            state.dark_mode = saved.dark_mode;
+        }
+        if self.input_backend.is_some() {
+            state.input_backend = saved.input_backend;
+        }
+        if self.hotkey_mode.is_some() {
+            state.hotkey_mode = saved.hotkey_mode;
+        }
+        if self.hotkey_backend.is_some() {
+            state.hotkey_backend = saved.hotkey_backend;
+        }
+        if self.hotkey_trigger.is_some() {
+            state.hotkey_trigger = saved.hotkey_trigger.clone();
+        }
+        if self.hotkey_timing.is_some() {
+            state.hotkey_timing = saved.hotkey_timing;
+        }
+        if self.hold_timing.is_some() {
+            state.hold_timing = saved.hold_timing;
+        }
+        if self.mouse_trigger.is_some() {
+            state.mouse_trigger = saved.mouse_trigger;
+        }
+        if self.mouse_timing.is_some() {
+            state.mouse_timing = saved.mouse_timing;
+        }
+        if self.macro_speed.is_some() {
+            state.macro_speed = saved.macro_speed;
+        }
+        if self.macro_loops.is_some() {
+            state.macro_loops = saved.macro_loops;
+        }
+        if self.keepalive_minutes.is_some() {
+            state.keepalive_minutes = saved.keepalive_minutes;
+        }
+        if self.keepalive_action.is_some() {
+            state.keepalive_action = saved.keepalive_action;
+        }
+        if self.idle_backend.is_some() {
+            state.idle_backend = saved.idle_backend;
+        }
+        if self.failsafe_corner.is_some() {
+            state.failsafe_corner = saved.failsafe_corner;
+        }
+        if self.max_clicks_per_second.is_some() {
+            state.max_clicks_per_second = saved.max_clicks_per_second;
+        }
+        if self.max_run_minutes.is_some() {
+            state.max_run_minutes = saved.max_run_minutes;
+        }
+        if self.max_session_clicks.is_some() {
+            state.max_session_clicks = saved.max_session_clicks;
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use super::*;
+use crate::failsafe::FailsafeCorner;
+use crate::hotkey::{HotkeyBackendKind, HotkeyMode, HotkeyTiming, MouseTrigger};
+use crate::idle::{IdleBackendKind, KeepaliveAction};
+use crate::input::BackendKind;
+use serial_test::serial;
+use std::env;
+use std::path::PathBuf;
+
+fn test_state() -> State {
+    State {
+        delay: 500,
+        start_delay: 5,
+        duration: 10,
+        use_start_delay: false,
+        use_duration: false,
+        dark_mode: true,
+        input_backend: BackendKind::Enigo,
+        hotkey_mode: HotkeyMode::Toggle,
+        hotkey_backend: HotkeyBackendKind::Auto,
+        hotkey_trigger: String::new(),
+        hotkey_timing: HotkeyTiming::Settings,
+        hold_timing: HotkeyTiming::Settings,
+        mouse_trigger: MouseTrigger::Off,
+        mouse_timing: HotkeyTiming::Settings,
+        macro_speed: 100,
+        macro_loops: 1,
+        jobs: Vec::new(),
+        profiles: Vec::new(),
+        profile: String::new(),
+        keepalive_minutes: 5,
+        keepalive_action: KeepaliveAction::Nudge,
+        idle_backend: IdleBackendKind::Auto,
+        failsafe_corner: FailsafeCorner::TopLeft,
+        max_clicks_per_second: 0,
+        max_run_minutes: 0,
+        max_session_clicks: 0,
+    }
+}
+
+#[test]
+#[serial]
+fn parse_flags() {
+    let cli = Cli::try_parse_from([
+        "turbo-clicker",
+        "--delay",
+        "100",
+        "--duration",
+        "30",
+        "--dark-mode",
+        "false",
+        "--input-backend",
+        "uinput",
+        "--hotkey-mode",
+        "hold",
+        "--hotkey-trigger",
+        "ALT+F8",
+        "--hotkey-timing",
+        "immediate",
+        "--hold-timing",
+        "ignore",
+        "--mouse-trigger",
+        "back",
+        "--mouse-timing",
+        "immediate",
+        "--macro-speed",
+        "150",
+        "--macro-loops",
+        "0",
+        "--keepalive-minutes",
+        "15",
+        "--keepalive-action",
+        "click",
+        "--failsafe-corner",
+        "bottom-right",
+        "--max-clicks-per-second",
+        "10",
+        "--max-session-clicks",
+        "5000",
+        "--save-overrides",
+    ])
+    .expect("Should parse arguments");
+
+    let expected = Overrides {
+        delay: Some(100),
+        duration: Some(30),
+        dark_mode: Some(false),
+        input_backend: Some(BackendKind::Uinput),
+        hotkey_mode: Some(HotkeyMode::Hold),
+        hotkey_trigger: Some("ALT+F8".to_string()),
+        hotkey_timing: Some(HotkeyTiming::Immediate),
+        hold_timing: Some(HotkeyTiming::Ignore),
+        mouse_trigger: Some(MouseTrigger::Back),
+        mouse_timing: Some(HotkeyTiming::Immediate),
+        macro_speed: Some(150),
+        macro_loops: Some(0),
+        keepalive_minutes: Some(15),
+        keepalive_action: Some(KeepaliveAction::Click),
+        failsafe_corner: Some(FailsafeCorner::BottomRight),
+        max_clicks_per_second: Some(10),
+        max_session_clicks: Some(5000),
+        save_overrides: true,
+        ..Default::default()
+    };
+    assert_eq!(expected, cli.overrides, "Overrides should match flags");
+}
+
+#[test]
+#[serial]
+fn parse_env_variables() {
+    unsafe {
+        env::set_var("TURBO_CLICKER_DELAY", "200");
+        env::set_var("TURBO_CLICKER_USE_START_DELAY", "yes");
+    }
+
+    let cli = Cli::try_parse_from(["turbo-clicker", "--delay", "300"]);
+
+    unsafe {
+        env::remove_var("TURBO_CLICKER_DELAY");
+        env::remove_var("TURBO_CLICKER_USE_START_DELAY");
+    }
+
+    let cli = cli.expect("Should parse arguments");
+    assert_eq!(
+        Some(300),
+        cli.overrides.delay,
+        "Flag should take precedence over environment"
+    );
+    assert_eq!(
+        Some(true),
+        cli.overrides.use_start_delay,
+        "Should read boolean from environment"
+    );
+}
+
+#[test]
+#[serial]
+fn parse_rejects_out_of_range_values() {
+    let res = Cli::try_parse_from(["turbo-clicker", "--delay", "5"]);
+    assert!(res.is_err(), "Delay below 20 ms should be rejected");
+
+    let res = Cli::try_parse_from(["turbo-clicker", "--duration", "0"]);
+    assert!(res.is_err(), "Duration of 0 s should be rejected");
+}
+
+#[test]
+fn overrides_is_empty() {
+    assert!(Overrides::default().is_empty(), "Default should be empty");
+
+    let overrides = Overrides {
+        save_overrides: true,
+        ..Default::default()
+    };
+    assert!(
+        overrides.is_empty(),
+        "save_overrides alone does not override anything"
+    );
+
+    let overrides = Overrides {
+        dark_mode: Some(false),
+        ..Default::default()
+    };
+    assert!(!overrides.is_empty(), "Should not be empty");
+}
+
+#[test]
+fn overrides_apply() {
+    let overrides = Overrides {
+        delay: Some(100),
+        duration: Some(30),
+        use_start_delay: Some(true),
+        ..Default::default()
+    };
+    let mut state = test_state();
+    overrides.apply(&mut state);
+
+    let expected = State {
+        delay: 100,
+        duration: 30,
+        use_duration: true,
+        use_start_delay: true,
+        ..test_state()
+    };
+    assert_eq!(expected, state, "Overrides should be applied");
+}
+
+#[test]
+fn overrides_apply_explicit_use_flag_wins() {
+    let overrides = Overrides {
+        duration: Some(30),
+        use_duration: Some(false),
+        ..Default::default()
+    };
+    let mut state = test_state();
+    overrides.apply(&mut state);
+
+    assert_eq!(30, state.duration, "Duration should be applied");
+    assert!(!state.use_duration, "Explicit use_duration should win");
+}
+
+#[test]
+fn overrides_revert() {
+    let overrides = Overrides {
+        delay: Some(100),
+        duration: Some(30),
+        ..Default::default()
+    };
+    let saved = test_state();
+    let mut state = State {
+        delay: 100,
+        duration: 30,
+        use_duration: true,
+        start_delay: 15,
+        ..test_state()
+    };
+    overrides.revert(&mut state, &saved);
+
+    let expected = State {
+        start_delay: 15,
+        ..test_state()
+    };
+    assert_eq!(
+        expected, state,
+        "Overridden settings should be reverted, others kept"
+    );
+}
+
+#[test]
+#[serial]
+fn parse_run_script() {
+    let cli = Cli::try_parse_from(["turbo-clicker", "--run-script", "farm.txt", "--delay", "50"])
+        .expect("Should parse arguments");
+    assert_eq!(Some(PathBuf::from("farm.txt")), cli.run_script);
+    assert_eq!(
+        Some(50),
+        cli.overrides.delay,
+        "Overrides should still apply"
+    );
+
+    let cli = Cli::try_parse_from(["turbo-clicker"]).expect("Should parse arguments");
+    assert_eq!(None, cli.run_script, "Should open the window by default");
+}
@@ -0,0 +1,151 @@
+use crate::input::{DeviceReader, InputBackend, open_devices};
+use evdev::{EventSummary, KeyCode};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[cfg(test)]
+mod test;
+
+/// How close to the corner the pointer has to be, in pixels.
+const CORNER_SIZE: i32 = 2;
+/// Pressing Escape this often within `ESCAPE_WINDOW` triggers the failsafe.
+pub const ESCAPE_PRESSES: usize = 3;
+const ESCAPE_WINDOW: Duration = Duration::from_secs(1);
+
+/// The screen corner that stops the autoclicker when the pointer is moved into it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum FailsafeCorner {
+    /// Only pressing Escape triggers the failsafe.
+    Off,
+    #[default]
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl FailsafeCorner {
+    /// Check if the position is in this corner of a screen with the given size.
+    pub fn contains(self, (x, y): (i32, i32), (width, height): (i32, i32)) -> bool {
+        let left = x < CORNER_SIZE;
+        let top = y < CORNER_SIZE;
+        let right = x >= width - CORNER_SIZE;
+        let bottom = y >= height - CORNER_SIZE;
+        match self {
+            FailsafeCorner::Off => false,
+            FailsafeCorner::TopLeft => top && left,
+            FailsafeCorner::TopRight => top && right,
+            FailsafeCorner::BottomLeft => bottom && left,
+            FailsafeCorner::BottomRight => bottom && right,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            FailsafeCorner::Off => "no",
+            FailsafeCorner::TopLeft => "top-left",
+            FailsafeCorner::TopRight => "top-right",
+            FailsafeCorner::BottomLeft => "bottom-left",
+            FailsafeCorner::BottomRight => "bottom-right",
+        }
+    }
+}
+
+/// Stops a run in an emergency, independent of the global hotkeys.
+/// It triggers when the pointer is in the chosen corner or Escape was pressed several times.
+/// Clones share the same state.
+#[derive(Clone)]
+pub struct Failsafe {
+    corner: Arc<Mutex<FailsafeCorner>>,
+    escapes: Arc<Mutex<Vec<Instant>>>,
+    /// Reads Escape from the keyboards, replaced when listening again.
+    reader: Arc<Mutex<Option<DeviceReader>>>,
+}
+
+impl Default for Failsafe {
+    /// Start without a corner, the app sets the one of the settings.
+    fn default() -> Self {
+        Self {
+            corner: Arc::new(Mutex::new(FailsafeCorner::Off)),
+            escapes: Arc::new(Mutex::new(Vec::new())),
+            reader: Arc::new(Mutex::new(None)),
+        }
+    }
+}
+
+impl Failsafe {
+    /// Use the given corner.
+    pub fn set_corner(&self, corner: FailsafeCorner) {
+        *self.corner.lock().unwrap() = corner;
+    }
+
+    /// Count the presses of Escape on every keyboard.
+    /// Requires read access to /dev/input, usually by being in the input group.
+    pub fn listen_escape(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let devices = open_devices(
+            |keys| keys.contains(KeyCode::KEY_ESC),
+            "no readable keyboard has an Escape key",
+        )?;
+        // The reader must not hold the failsafe, or the failsafe would keep its own reader alive.
+        let escapes = Arc::clone(&self.escapes);
+        let reader = DeviceReader::spawn(devices, move |event| {
+            if let EventSummary::Key(_, KeyCode::KEY_ESC, 1) = event.destructure() {
+                count_escape(&escapes);
+            }
+            true
+        });
+        // Dropping the previous reader stops it, so every press is only counted once.
+        *self.reader.lock().unwrap() = Some(reader);
+        Ok(())
+    }
+
+    /// Count a press of Escape as if it was read from a keyboard.
+    #[cfg(test)]
+    pub fn escape_pressed(&self) {
+        count_escape(&self.escapes);
+    }
+
+    /// Forget the earlier presses of Escape, so they don't stop a new run.
+    pub fn reset(&self) {
+        self.escapes.lock().unwrap().clear();
+    }
+
+    /// Check if the failsafe was triggered, returning the reason to stop.
+    /// The corner only works with backends that can report the pointer position.
+    pub fn check(&self, input: &dyn InputBackend) -> Result<(), String> {
+        let escapes = self
+            .escapes
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|pressed| pressed.elapsed() < ESCAPE_WINDOW)
+            .count();
+        if escapes >= ESCAPE_PRESSES {
+            return Err(format!("failsafe, Escape was pressed {escapes} times"));
+        }
+
+        let corner = *self.corner.lock().unwrap();
+        if corner == FailsafeCorner::Off {
+            return Ok(());
+        }
+        if let (Ok(position), Ok(size)) = (input.location(), input.screen_size())
+            && corner.contains(position, size)
+        {
+            return Err(format!(
+                "failsafe, the pointer was moved into the {} corner",
+                corner.name()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Count a press of Escape in the presses, only the recent ones are kept.
+fn count_escape(escapes: &Mutex<Vec<Instant>>) {
+    let mut escapes = escapes.lock().unwrap();
+    escapes.retain(|pressed| pressed.elapsed() < ESCAPE_WINDOW);
+    escapes.push(Instant::now());
+}
@@ -0,0 +1,70 @@
+use super::*;
+use crate::input::recording::{RecordingBackend, SCREEN_SIZE};
+
+#[test]
+fn corner_contains_only_its_corner() {
+    let (width, height) = SCREEN_SIZE;
+    assert!(FailsafeCorner::TopLeft.contains((0, 1), SCREEN_SIZE));
+    assert!(!FailsafeCorner::TopLeft.contains((5, 0), SCREEN_SIZE));
+    assert!(FailsafeCorner::TopRight.contains((width - 1, 0), SCREEN_SIZE));
+    assert!(FailsafeCorner::BottomLeft.contains((0, height - 1), SCREEN_SIZE));
+    assert!(FailsafeCorner::BottomRight.contains((width - 1, height - 2), SCREEN_SIZE));
+    assert!(!FailsafeCorner::BottomRight.contains((0, 0), SCREEN_SIZE));
+    assert!(!FailsafeCorner::Off.contains((0, 0), SCREEN_SIZE));
+}
+
+#[test]
+fn check_triggers_in_corner() {
+    let backend = RecordingBackend::new();
+    let failsafe = Failsafe::default();
+    assert_eq!(
+        Ok(()),
+        failsafe.check(&backend),
+        "No corner should be used by default"
+    );
+
+    failsafe.set_corner(FailsafeCorner::TopRight);
+    backend.set_location(SCREEN_SIZE.0 - 1, 0);
+    let reason = failsafe.check(&backend).expect_err("Should trigger");
+    assert!(reason.contains("top-right"), "Got: {reason}");
+
+    backend.set_location(500, 0);
+    assert_eq!(Ok(()), failsafe.check(&backend));
+}
+
+#[test]
+fn check_triggers_after_repeated_escape() {
+    let backend = RecordingBackend::new();
+    let failsafe = Failsafe::default();
+    for _ in 1..ESCAPE_PRESSES {
+        failsafe.escape_pressed();
+    }
+    assert_eq!(
+        Ok(()),
+        failsafe.check(&backend),
+        "Should need {ESCAPE_PRESSES} presses"
+    );
+
+    failsafe.escape_pressed();
+    let reason = failsafe.check(&backend).expect_err("Should trigger");
+    assert!(reason.contains("Escape"), "Got: {reason}");
+
+    failsafe.reset();
+    assert_eq!(
+        Ok(()),
+        failsafe.check(&backend),
+        "Reset should forget presses"
+    );
+}
+
+#[test]
+fn corner_serde_names() {
+    assert_eq!(
+        "\"bottom-left\"",
+        serde_json::to_string(&FailsafeCorner::BottomLeft).unwrap()
+    );
+    assert_eq!(
+        FailsafeCorner::Off,
+        serde_json::from_str::<FailsafeCorner>("\"off\"").unwrap()
+    );
+}
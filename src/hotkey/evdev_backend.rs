@@ -0,0 +1,189 @@
+use super::{HotkeyAction, HotkeyEvent, Modifier, describe_bindings, split_trigger, trigger_for};
+use crate::input::{DeviceReader, open_devices};
+use evdev::{EventSummary, KeyCode};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc::UnboundedSender;
+
+const MODIFIER_KEYS: [KeyCode; 8] = [
+    KeyCode::KEY_LEFTCTRL,
+    KeyCode::KEY_RIGHTCTRL,
+    KeyCode::KEY_LEFTSHIFT,
+    KeyCode::KEY_RIGHTSHIFT,
+    KeyCode::KEY_LEFTALT,
+    KeyCode::KEY_RIGHTALT,
+    KeyCode::KEY_LEFTMETA,
+    KeyCode::KEY_RIGHTMETA,
+];
+
+/// Global hotkeys read directly from the input devices, this works with any compositor.
+/// Requires read access to /dev/input, usually by being in the input group.
+#[derive(Clone)]
+pub struct EvdevHotkeys {
+    bindings: Vec<String>,
+    reader: Arc<Mutex<Option<DeviceReader>>>,
+}
+
+impl EvdevHotkeys {
+    /// Listen for the trigger of every action on all input devices and send their events to `events`.
+    /// The toggle action uses `toggle_trigger`, or its default if it is empty.
+    /// Returns an error if no readable input device has the keys of the triggers.
+    pub fn listen(
+        toggle_trigger: &str,
+        events: UnboundedSender<HotkeyEvent>,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let mut triggers = Vec::new();
+        let mut bound = Vec::new();
+        for action in HotkeyAction::ALL {
+            let trigger = trigger_for(action, toggle_trigger);
+            match parse_trigger(trigger) {
+                Ok(parsed) => {
+                    triggers.push((parsed, action));
+                    bound.push((action.id(), trigger));
+                }
+                Err(e) => eprintln!("Invalid hotkey {trigger} for {action:?}: {e}"),
+            }
+        }
+
+        let reader = spawn_readers(triggers, events)?;
+        Ok(Self {
+            bindings: describe_bindings(bound),
+            reader: Arc::new(Mutex::new(Some(reader))),
+        })
+    }
+
+    /// Stop reading the input devices and wait until they are closed.
+    pub async fn close(&self) {
+        let reader = self.reader.lock().unwrap().take();
+        if let Some(reader) = reader
+            && let Err(e) = tokio::task::spawn_blocking(move || reader.stop()).await
+        {
+            eprintln!("Failed to stop reading the hotkeys: {e}");
+        }
+    }
+
+    /// Return a description of the trigger bound to each hotkey.
+    pub fn bindings(&self) -> Vec<String> {
+        self.bindings.clone()
+    }
+}
+
+/// Listen for presses of a single key or button without modifiers on all input devices.
+/// Listening stops once the returned reader is stopped or dropped.
+pub(super) fn listen_key(
+    name: &str,
+    action: HotkeyAction,
+    events: UnboundedSender<HotkeyEvent>,
+) -> Result<DeviceReader, Box<dyn Error + Send + Sync>> {
+    spawn_readers(vec![(parse_trigger(name)?, action)], events)
+}
+
+/// Start reading the key events of every input device that can send one of the triggers.
+fn spawn_readers(
+    triggers: Vec<(Trigger, HotkeyAction)>,
+    events: UnboundedSender<HotkeyEvent>,
+) -> Result<DeviceReader, Box<dyn Error + Send + Sync>> {
+    let mut keys: HashSet<KeyCode> = triggers.iter().map(|(trigger, _)| trigger.key).collect();
+    if triggers
+        .iter()
+        .any(|(trigger, _)| !trigger.modifiers.is_empty())
+    {
+        keys.extend(MODIFIER_KEYS);
+    }
+    let devices = open_devices(
+        |supported| keys.iter().any(|key| supported.contains(*key)),
+        "no readable input device has the hotkeys",
+    )?;
+
+    // Modifiers and triggers can be on different devices, e.g. CTRL on the keyboard and a mouse button.
+    let matcher = Arc::new(Mutex::new(Matcher::new(triggers)));
+    // Reading stops once the receiver of the events is gone.
+    Ok(DeviceReader::spawn(devices, move |event| {
+        let EventSummary::Key(_, key, value) = event.destructure() else {
+            return true;
+        };
+        let hotkey_event = matcher.lock().unwrap().handle(key, value);
+        hotkey_event.is_none_or(|hotkey_event| events.send(hotkey_event).is_ok())
+    }))
+}
+
+/// A key, optionally combined with modifiers.
+#[derive(Debug, PartialEq)]
+pub(super) struct Trigger {
+    modifiers: HashSet<Modifier>,
+    key: KeyCode,
+}
+
+/// Turns the key events of all devices into hotkey events.
+pub(super) struct Matcher {
+    triggers: Vec<(Trigger, HotkeyAction)>,
+    modifiers: HashSet<KeyCode>,
+    held: HashMap<KeyCode, HotkeyAction>,
+}
+
+impl Matcher {
+    pub(super) fn new(triggers: Vec<(Trigger, HotkeyAction)>) -> Self {
+        Self {
+            triggers,
+            modifiers: HashSet::new(),
+            held: HashMap::new(),
+        }
+    }
+
+    /// Handle a key event, the value is 1 for a press, 0 for a release and 2 for a repeat.
+    pub(super) fn handle(&mut self, key: KeyCode, value: i32) -> Option<HotkeyEvent> {
+        if MODIFIER_KEYS.contains(&key) {
+            if value == 0 {
+                self.modifiers.remove(&key);
+            } else {
+                self.modifiers.insert(key);
+            }
+            return None;
+        }
+        match value {
+            1 => {
+                let modifiers: HashSet<Modifier> =
+                    self.modifiers.iter().map(|key| modifier_of(*key)).collect();
+                let (_, action) = self
+                    .triggers
+                    .iter()
+                    .find(|(trigger, _)| trigger.key == key && trigger.modifiers == modifiers)?;
+                self.held.insert(key, *action);
+                Some(HotkeyEvent::Pressed(*action))
+            }
+            // The modifiers might already be released, so only the key identifies the hotkey.
+            0 => self.held.remove(&key).map(HotkeyEvent::Released),
+            _ => None,
+        }
+    }
+}
+
+fn modifier_of(key: KeyCode) -> Modifier {
+    match key {
+        KeyCode::KEY_LEFTCTRL | KeyCode::KEY_RIGHTCTRL => Modifier::Ctrl,
+        KeyCode::KEY_LEFTSHIFT | KeyCode::KEY_RIGHTSHIFT => Modifier::Shift,
+        KeyCode::KEY_LEFTALT | KeyCode::KEY_RIGHTALT => Modifier::Alt,
+        _ => Modifier::Logo,
+    }
+}
+
+/// Parse a trigger like "CTRL+SHIFT+F12" or "BTN_SIDE" into its modifiers and key.
+/// Keys can be given by their evdev name or by the X11 name the portal uses.
+pub(super) fn parse_trigger(trigger: &str) -> Result<Trigger, String> {
+    let (modifiers, key) = split_trigger(trigger)?;
+    let name = match key.to_uppercase().as_str() {
+        "RETURN" => "ENTER".to_string(),
+        "ESCAPE" => "ESC".to_string(),
+        "PRINT" => "SYSRQ".to_string(),
+        name => name.to_string(),
+    };
+    let key = KeyCode::from_str(&name)
+        .or_else(|_| KeyCode::from_str(&format!("KEY_{}", name.replace('_', ""))))
+        .map_err(|_| format!("unknown key \"{key}\" in \"{trigger}\""))?;
+    Ok(Trigger {
+        modifiers: modifiers.into_iter().collect(),
+        key,
+    })
+}
@@ -0,0 +1,414 @@
+use crate::input::DeviceReader;
+use enigo::Button;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::error::Error;
+use tokio::sync::mpsc::UnboundedSender;
+
+mod evdev_backend;
+mod portal;
+mod x11;
+
+#[cfg(test)]
+mod test;
+
+pub use evdev_backend::EvdevHotkeys;
+pub use portal::HotkeyPortal;
+pub use x11::X11Hotkeys;
+
+/// The actions that can be bound to a global shortcut.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HotkeyAction {
+    Start,
+    Stop,
+    Pause,
+    Toggle,
+    RaiseDelay,
+    LowerDelay,
+    NextProfile,
+    ToggleWindow,
+    /// The mouse button trigger, it works like Toggle but is not a shortcut.
+    MouseToggle,
+}
+
+impl HotkeyAction {
+    /// All actions, in the order they are shown to the user.
+    pub const ALL: [HotkeyAction; 8] = [
+        HotkeyAction::Toggle,
+        HotkeyAction::Start,
+        HotkeyAction::Stop,
+        HotkeyAction::Pause,
+        HotkeyAction::RaiseDelay,
+        HotkeyAction::LowerDelay,
+        HotkeyAction::NextProfile,
+        HotkeyAction::ToggleWindow,
+    ];
+
+    /// The id of the shortcut, this is how the portal remembers the binding.
+    pub fn id(&self) -> &'static str {
+        match self {
+            // Keep the id of the original single shortcut, so existing bindings still work.
+            HotkeyAction::Toggle => "Turbo Clicker Trigger",
+            HotkeyAction::Start => "Turbo Clicker Start",
+            HotkeyAction::Stop => "Turbo Clicker Stop",
+            HotkeyAction::Pause => "Turbo Clicker Pause",
+            HotkeyAction::RaiseDelay => "Turbo Clicker Raise Delay",
+            HotkeyAction::LowerDelay => "Turbo Clicker Lower Delay",
+            HotkeyAction::NextProfile => "Turbo Clicker Next Profile",
+            HotkeyAction::ToggleWindow => "Turbo Clicker Toggle Window",
+            HotkeyAction::MouseToggle => "Turbo Clicker Mouse Trigger",
+        }
+    }
+
+    /// Find the action for the given shortcut id.
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.id() == id)
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            HotkeyAction::Toggle => "Start/stop the autoclicker",
+            HotkeyAction::Start => "Start the autoclicker",
+            HotkeyAction::Stop => "Stop the autoclicker",
+            HotkeyAction::Pause => "Pause/resume the autoclicker",
+            HotkeyAction::RaiseDelay => "Raise the delay between clicks",
+            HotkeyAction::LowerDelay => "Lower the delay between clicks",
+            HotkeyAction::NextProfile => "Switch to the next profile",
+            HotkeyAction::ToggleWindow => "Show/hide the window",
+            HotkeyAction::MouseToggle => "Start/stop the autoclicker with the mouse button",
+        }
+    }
+
+    fn preferred_trigger(&self) -> &'static str {
+        match self {
+            HotkeyAction::Toggle => "CTRL+SHIFT+F12",
+            HotkeyAction::Start => "CTRL+SHIFT+F9",
+            HotkeyAction::Stop => "CTRL+SHIFT+F10",
+            HotkeyAction::Pause => "CTRL+SHIFT+F8",
+            HotkeyAction::RaiseDelay => "CTRL+SHIFT+Page_Up",
+            HotkeyAction::LowerDelay => "CTRL+SHIFT+Page_Down",
+            HotkeyAction::NextProfile => "CTRL+SHIFT+F7",
+            HotkeyAction::ToggleWindow => "CTRL+SHIFT+F11",
+            // The button is chosen in the settings, see MouseTrigger.
+            HotkeyAction::MouseToggle => "",
+        }
+    }
+}
+
+/// How the toggle shortcut controls the autoclicker.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum HotkeyMode {
+    /// Start clicking on the first press and stop on the next one.
+    #[default]
+    Toggle,
+    /// Click while the shortcut is held down.
+    Hold,
+}
+
+/// Which timing settings of the main page apply to runs started by a trigger.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum HotkeyTiming {
+    /// Use the start delay and duration, like the start button.
+    #[default]
+    Settings,
+    /// Start clicking immediately, but still stop after the duration.
+    Immediate,
+    /// Ignore both the start delay and the duration.
+    Ignore,
+}
+
+/// The start delay and duration of runs started by a trigger.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RunTiming {
+    /// Seconds to wait before clicking.
+    pub start_delay: Option<u64>,
+    /// Seconds after which to stop clicking.
+    pub duration: Option<u64>,
+}
+
+/// The settings that control runs started by a shortcut or the mouse button, kept in sync with the UI.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct HotkeySettings {
+    pub mode: HotkeyMode,
+    /// Runs started by the start or toggle shortcut.
+    pub hotkey: RunTiming,
+    /// Runs started by holding the toggle shortcut in hold mode.
+    pub hold: RunTiming,
+    /// Runs started by the mouse button, whatever the mode.
+    pub mouse: RunTiming,
+}
+
+impl HotkeySettings {
+    /// The timing of runs started by the action.
+    pub fn timing(&self, action: HotkeyAction) -> RunTiming {
+        match (action, self.mode) {
+            (HotkeyAction::MouseToggle, _) => self.mouse,
+            (HotkeyAction::Toggle, HotkeyMode::Hold) => self.hold,
+            _ => self.hotkey,
+        }
+    }
+}
+
+/// The physical mouse button that starts/stops the autoclicker like the toggle shortcut.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum MouseTrigger {
+    /// No mouse button is used.
+    #[default]
+    Off,
+    /// The back side button.
+    Back,
+    /// The forward side button.
+    Forward,
+    /// The middle button.
+    Middle,
+}
+
+impl MouseTrigger {
+    /// The mouse button used as trigger.
+    pub fn button(&self) -> Option<Button> {
+        match self {
+            MouseTrigger::Off => None,
+            MouseTrigger::Back => Some(Button::Back),
+            MouseTrigger::Forward => Some(Button::Forward),
+            MouseTrigger::Middle => Some(Button::Middle),
+        }
+    }
+
+    /// The evdev name of the button.
+    fn key_name(&self) -> Option<&'static str> {
+        match self {
+            MouseTrigger::Off => None,
+            MouseTrigger::Back => Some("BTN_SIDE"),
+            MouseTrigger::Forward => Some("BTN_EXTRA"),
+            MouseTrigger::Middle => Some("BTN_MIDDLE"),
+        }
+    }
+
+    /// Listen for the button on the input devices and send it to `events` as `HotkeyAction::MouseToggle`.
+    /// Does nothing if no button is used.
+    /// Listening stops once the returned reader is stopped or dropped.
+    pub fn listen(
+        &self,
+        events: UnboundedSender<HotkeyEvent>,
+    ) -> Result<Option<DeviceReader>, Box<dyn Error + Send + Sync>> {
+        self.key_name()
+            .map(|name| evdev_backend::listen_key(name, HotkeyAction::MouseToggle, events))
+            .transpose()
+    }
+}
+
+/// Whether the hotkey backend is working.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HotkeyStatus {
+    Active,
+    /// The backend lost its session and is trying to restore it.
+    Reconnecting {
+        attempt: u32,
+        error: String,
+    },
+}
+
+impl HotkeyStatus {
+    pub fn is_active(&self) -> bool {
+        matches!(self, HotkeyStatus::Active)
+    }
+
+    /// Describe the status for the UI, empty while the hotkeys work.
+    pub fn describe(&self) -> String {
+        match self {
+            HotkeyStatus::Active => String::new(),
+            HotkeyStatus::Reconnecting { attempt, error } => {
+                format!("Lost the hotkey session ({error}), reconnecting (attempt {attempt})")
+            }
+        }
+    }
+}
+
+/// A press or release of one of the shortcuts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HotkeyEvent {
+    Pressed(HotkeyAction),
+    Released(HotkeyAction),
+}
+
+/// The hotkey backends the user can choose from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum HotkeyBackendKind {
+    /// Use the GlobalShortcuts portal, fall back to X11 on X11 sessions if it is not available.
+    #[default]
+    Auto,
+    /// Always use the GlobalShortcuts portal.
+    Portal,
+    /// Always grab the keys from the X server.
+    X11,
+    /// Read the keys directly from the input devices, works on any compositor.
+    Evdev,
+}
+
+/// The running hotkey backend.
+#[derive(Clone)]
+pub enum Hotkeys {
+    Portal(HotkeyPortal),
+    X11(X11Hotkeys),
+    Evdev(EvdevHotkeys),
+}
+
+impl Hotkeys {
+    /// Start the hotkey backend of the given kind and send its events to `events`.
+    /// The X11 and evdev backends bind `trigger` to the toggle action, or the default if it is empty.
+    pub async fn register(
+        kind: HotkeyBackendKind,
+        trigger: &str,
+        events: UnboundedSender<HotkeyEvent>,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        match kind {
+            HotkeyBackendKind::Auto => match Self::register_portal(events.clone()).await {
+                Ok(hotkeys) => Ok(hotkeys),
+                // With XWayland the grabs succeed, but only work while an X11 window has focus.
+                Err(e) if !is_x11_session() => Err(e),
+                Err(e) => {
+                    eprintln!("Failed to register portal hotkeys, falling back to X11: {e}");
+                    match X11Hotkeys::grab(trigger, events) {
+                        Ok(hotkeys) => Ok(Hotkeys::X11(hotkeys)),
+                        Err(x11_err) => Err(format!("{e} (X11 fallback failed: {x11_err})").into()),
+                    }
+                }
+            },
+            HotkeyBackendKind::Portal => Self::register_portal(events).await,
+            HotkeyBackendKind::X11 => Ok(Hotkeys::X11(X11Hotkeys::grab(trigger, events)?)),
+            HotkeyBackendKind::Evdev => Ok(Hotkeys::Evdev(EvdevHotkeys::listen(trigger, events)?)),
+        }
+    }
+
+    async fn register_portal(
+        events: UnboundedSender<HotkeyEvent>,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let portal = HotkeyPortal::register().await?;
+        portal.forward_events(events);
+        Ok(Hotkeys::Portal(portal))
+    }
+
+    /// Report the triggers bound to the hotkeys now and whenever they are changed.
+    pub fn watch_bindings<F>(&self, on_change: F)
+    where
+        F: Fn(Vec<String>) + Send + 'static,
+    {
+        match self {
+            Hotkeys::Portal(portal) => portal.watch_bindings(on_change),
+            // X11 grabs and evdev triggers are fixed until the app is restarted.
+            Hotkeys::X11(x11) => on_change(x11.bindings()),
+            Hotkeys::Evdev(evdev) => on_change(evdev.bindings()),
+        }
+    }
+
+    /// Report whether the hotkeys work now and whenever it changes.
+    pub fn watch_status<F>(&self, on_change: F)
+    where
+        F: Fn(HotkeyStatus) + Send + 'static,
+    {
+        match self {
+            Hotkeys::Portal(portal) => portal.watch_status(on_change),
+            // Grabs and devices are not re-created, failures are only logged.
+            Hotkeys::X11(_) | Hotkeys::Evdev(_) => on_change(HotkeyStatus::Active),
+        }
+    }
+
+    /// Check if the bindings can be changed with `configure`.
+    pub fn can_configure(&self) -> bool {
+        matches!(self, Hotkeys::Portal(_))
+    }
+
+    /// Open the dialog of the portal to configure the hotkeys.
+    pub async fn configure(&self) {
+        match self {
+            Hotkeys::Portal(portal) => portal.configure_hotkey().await,
+            Hotkeys::X11(_) | Hotkeys::Evdev(_) => {
+                eprintln!("X11 and evdev hotkeys are configured in the settings")
+            }
+        }
+    }
+
+    /// Unregister the hotkeys, before the app quits or they are registered again.
+    pub async fn close(&self) {
+        match self {
+            Hotkeys::Portal(portal) => portal.close().await,
+            Hotkeys::X11(x11) => x11.close(),
+            Hotkeys::Evdev(evdev) => evdev.close().await,
+        }
+    }
+}
+
+/// Check if the desktop session runs on X11, and not on Wayland with XWayland.
+fn is_x11_session() -> bool {
+    x11_session(
+        env::var("XDG_SESSION_TYPE").ok().as_deref(),
+        env::var_os("WAYLAND_DISPLAY").is_some(),
+    )
+}
+
+/// Check the session type, without it a Wayland display means the session runs on Wayland.
+fn x11_session(session_type: Option<&str>, wayland_display: bool) -> bool {
+    match session_type {
+        Some("x11") => true,
+        Some("wayland") => false,
+        _ => !wayland_display,
+    }
+}
+
+/// A modifier key of a trigger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Modifier {
+    Ctrl,
+    Shift,
+    Alt,
+    Logo,
+}
+
+/// Split a trigger like "CTRL+SHIFT+F12" into its modifiers and the name of the key.
+fn split_trigger(trigger: &str) -> Result<(Vec<Modifier>, &str), String> {
+    let mut parts: Vec<&str> = trigger.split('+').map(str::trim).collect();
+    let key = parts
+        .pop()
+        .filter(|key| !key.is_empty())
+        .ok_or_else(|| format!("missing key in \"{trigger}\""))?;
+    let modifiers = parts
+        .into_iter()
+        .map(|modifier| match modifier.to_uppercase().as_str() {
+            "CTRL" | "CONTROL" => Ok(Modifier::Ctrl),
+            "SHIFT" => Ok(Modifier::Shift),
+            "ALT" => Ok(Modifier::Alt),
+            "LOGO" | "SUPER" => Ok(Modifier::Logo),
+            _ => Err(format!("unknown modifier \"{modifier}\" in \"{trigger}\"")),
+        })
+        .collect::<Result<_, _>>()?;
+    Ok((modifiers, key))
+}
+
+/// The trigger of the action, the toggle action uses `toggle_trigger` unless it is empty.
+fn trigger_for(action: HotkeyAction, toggle_trigger: &str) -> &str {
+    match action {
+        HotkeyAction::Toggle if !toggle_trigger.is_empty() => toggle_trigger,
+        _ => action.preferred_trigger(),
+    }
+}
+
+/// Describe the trigger bound to each action, or "not bound" if the backend has no trigger for it.
+fn describe_bindings<'a>(bound: impl IntoIterator<Item = (&'a str, &'a str)>) -> Vec<String> {
+    let bound: Vec<(&str, &str)> = bound.into_iter().collect();
+    HotkeyAction::ALL
+        .iter()
+        .map(|action| {
+            let trigger = bound
+                .iter()
+                .find(|(id, _)| *id == action.id())
+                .map(|(_, trigger)| *trigger)
+                .filter(|trigger| !trigger.is_empty())
+                .unwrap_or("not bound");
+            format!("{}: {trigger}", action.description())
+        })
+        .collect()
+}
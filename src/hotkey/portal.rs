@@ -0,0 +1,272 @@
+use super::{HotkeyAction, HotkeyEvent, HotkeyStatus, describe_bindings};
+use ashpd::Error;
+use ashpd::desktop::Session;
+use ashpd::desktop::global_shortcuts::{GlobalShortcuts, NewShortcut, Shortcut};
+use futures_util::future::ready;
+use futures_util::{Stream, StreamExt, stream};
+use std::pin::pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::{Mutex, watch};
+use tokio::time::sleep;
+
+/// The longest time to wait between attempts to re-create the session.
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Wrapper around GlobalShortcuts
+#[derive(Clone)]
+pub struct HotkeyPortal {
+    portal: Arc<Mutex<GlobalShortcuts>>,
+    session: Arc<Mutex<Arc<Session<GlobalShortcuts>>>>,
+    status: watch::Sender<HotkeyStatus>,
+    /// Set once the session is closed on purpose, so it is not re-created.
+    closed: Arc<AtomicBool>,
+}
+
+impl HotkeyPortal {
+    /// Register a global hotkey for every action.
+    pub async fn register() -> Result<Self, Error> {
+        let portal = GlobalShortcuts::new().await?;
+        let session = bind_all(&portal).await?;
+        Ok(Self {
+            portal: Arc::new(Mutex::new(portal)),
+            session: Arc::new(Mutex::new(Arc::new(session))),
+            status: watch::Sender::new(HotkeyStatus::Active),
+            closed: Arc::new(AtomicBool::new(false)),
+        })
+    }
+    /// Send an event to `events` whenever one of the hotkeys is pressed or released.
+    /// If the session is closed, e.g. because the portal was restarted, it is re-created with backoff.
+    pub fn forward_events(&self, events: UnboundedSender<HotkeyEvent>) {
+        let hotkey = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let error = match hotkey.forward_until_closed(&events).await {
+                    Ok(true) => return,
+                    Ok(false) => "the session was closed".to_string(),
+                    Err(e) => e.to_string(),
+                };
+                if hotkey.closed.load(Ordering::Acquire) {
+                    return;
+                }
+                eprintln!("Lost the hotkey portal session, re-creating it: {error}");
+                if !hotkey.reconnect(error).await {
+                    return;
+                }
+            }
+        });
+    }
+    /// Forward the hotkey events until the session or the event stream is closed.
+    /// Returns true if the receiver of the events is gone.
+    async fn forward_until_closed(
+        &self,
+        events: &UnboundedSender<HotkeyEvent>,
+    ) -> Result<bool, Error> {
+        let session = Arc::clone(&*self.session.lock().await);
+        let mut closed = pin!(session.receive_closed().await?);
+        let mut stream = pin!(self.event_stream().await?);
+        loop {
+            tokio::select! {
+                event = stream.next() => match event {
+                    Some(event) => {
+                        if events.send(event).is_err() {
+                            return Ok(true);
+                        }
+                    }
+                    None => return Ok(false),
+                },
+                _ = closed.next() => return Ok(false),
+            }
+        }
+    }
+    /// Re-create the session and bind the hotkeys again, retrying with increasing delay until it works.
+    /// Returns false if the session was closed on purpose in the meantime.
+    async fn reconnect(&self, mut error: String) -> bool {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            self.status.send_replace(HotkeyStatus::Reconnecting {
+                attempt,
+                error: error.clone(),
+            });
+            sleep(backoff(attempt)).await;
+            if self.closed.load(Ordering::Acquire) {
+                return false;
+            }
+            match self.recreate_session().await {
+                Ok(true) => {
+                    println!("Re-created the hotkey portal session");
+                    self.status.send_replace(HotkeyStatus::Active);
+                    return true;
+                }
+                Ok(false) => return false,
+                Err(e) => {
+                    eprintln!("Failed to re-create the hotkey portal session: {e}");
+                    error = e.to_string();
+                }
+            }
+        }
+    }
+
+    /// Connect to the portal again and bind all hotkeys in a new session.
+    /// Returns false and closes the new session if the old one was closed on purpose meanwhile.
+    async fn recreate_session(&self) -> Result<bool, Error> {
+        let portal = GlobalShortcuts::new().await?;
+        let session = bind_all(&portal).await?;
+        let mut current_portal = self.portal.lock().await;
+        let mut current_session = self.session.lock().await;
+        // close() sets the flag before it takes the session, so it either sees the flag here or closes the new session.
+        if self.closed.load(Ordering::Acquire) {
+            drop(current_session);
+            drop(current_portal);
+            if let Err(e) = session.close().await {
+                eprintln!("Failed to close the re-created hotkey portal session: {e}");
+            }
+            return Ok(false);
+        }
+        *current_portal = portal;
+        *current_session = Arc::new(session);
+        Ok(true)
+    }
+    /// Report the status of the session now and whenever it changes.
+    pub fn watch_status<F>(&self, on_change: F)
+    where
+        F: Fn(HotkeyStatus) + Send + 'static,
+    {
+        let mut status = self.status.subscribe();
+        tokio::spawn(async move {
+            loop {
+                let current = status.borrow_and_update().clone();
+                on_change(current);
+                if status.changed().await.is_err() {
+                    return;
+                }
+            }
+        });
+    }
+    /// Return a stream of events when one of the hotkeys is pressed or released.
+    async fn event_stream(&self) -> Result<impl Stream<Item = HotkeyEvent> + use<>, Error> {
+        let portal = self.portal.lock().await;
+        let pressed = portal.receive_activated().await?.filter_map(|activated| {
+            ready(HotkeyAction::from_id(activated.shortcut_id()).map(HotkeyEvent::Pressed))
+        });
+        let released = portal
+            .receive_deactivated()
+            .await?
+            .filter_map(|deactivated| {
+                ready(HotkeyAction::from_id(deactivated.shortcut_id()).map(HotkeyEvent::Released))
+            });
+        Ok(stream::select(pressed, released))
+    }
+    /// Report the triggers bound to the hotkeys now and whenever they are changed.
+    /// The bindings are listed again after the session has been re-created.
+    pub fn watch_bindings<F>(&self, mut on_change: F)
+    where
+        F: Fn(Vec<String>) + Send + 'static,
+    {
+        let hotkey = self.clone();
+        let mut status = self.status.subscribe();
+        tokio::spawn(async move {
+            loop {
+                if status.wait_for(HotkeyStatus::is_active).await.is_err() {
+                    return;
+                }
+                tokio::select! {
+                    _ = hotkey.report_bindings(&mut on_change) => (),
+                    _ = status.wait_for(|status| !status.is_active()) => (),
+                }
+                if status.wait_for(|status| !status.is_active()).await.is_err() {
+                    return;
+                }
+            }
+        });
+    }
+    /// Report the triggers bound to the hotkeys until the portal stops sending changes.
+    /// Takes `on_change` mutably, so the future is Send without requiring F to be Sync.
+    async fn report_bindings<F>(&self, on_change: &mut F)
+    where
+        F: Fn(Vec<String>),
+    {
+        // Subscribe before listing, so no change is missed in between.
+        let changed = self.portal.lock().await.receive_shortcuts_changed().await;
+        match self.list_bindings().await {
+            Ok(bindings) => on_change(bindings),
+            Err(e) => eprintln!("Failed to list bound hotkeys: {e}"),
+        }
+        let mut stream = match changed {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("Failed to receive hotkey changes: {e}");
+                return;
+            }
+        };
+        while let Some(changed) = stream.next().await {
+            on_change(describe(changed.shortcuts()));
+        }
+    }
+    /// Return a description of the trigger bound to each hotkey.
+    async fn list_bindings(&self) -> Result<Vec<String>, Error> {
+        let portal = self.portal.lock().await;
+        let session = self.session.lock().await;
+        let request = portal.list_shortcuts(&session, Default::default()).await?;
+        Ok(describe(request.response()?.shortcuts()))
+    }
+    /// Close the session, so the desktop removes the hotkeys of the app right away.
+    pub async fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+        let session = Arc::clone(&*self.session.lock().await);
+        match session.close().await {
+            Ok(()) => println!("Closed the hotkey portal session"),
+            Err(e) => eprintln!("Failed to close the hotkey portal session: {e}"),
+        }
+    }
+    /// Open dialog to configure the hotkeys.
+    pub async fn configure_hotkey(&self) {
+        let portal = self.portal.lock().await;
+        let session = self.session.lock().await;
+        match portal
+            .configure_shortcuts(&session, None, Default::default())
+            .await
+        {
+            Ok(_) => (),
+            Err(e) => eprintln!("Failed to open hotkey configuration dialog: {e}"),
+        };
+    }
+}
+
+/// Create a session and bind a global hotkey for every action in it.
+async fn bind_all(portal: &GlobalShortcuts) -> Result<Session<GlobalShortcuts>, Error> {
+    let session = portal.create_session(Default::default()).await?;
+    let hotkeys: Vec<NewShortcut> = HotkeyAction::ALL.into_iter().map(new_shortcut).collect();
+    let request = portal
+        .bind_shortcuts(&session, &hotkeys, None, Default::default())
+        .await?;
+    match request.response() {
+        Ok(bound) => println!("Bound hotkeys: {}", describe(bound.shortcuts()).join(", ")),
+        Err(e) => eprintln!("The portal did not bind the hotkeys: {e}"),
+    }
+    Ok(session)
+}
+
+/// The time to wait before the given attempt to re-create the session, doubling up to a minute.
+pub(super) fn backoff(attempt: u32) -> Duration {
+    let secs = 1u64
+        .checked_shl(attempt.saturating_sub(1))
+        .unwrap_or(u64::MAX);
+    Duration::from_secs(secs).min(MAX_BACKOFF)
+}
+
+fn describe(shortcuts: &[Shortcut]) -> Vec<String> {
+    describe_bindings(
+        shortcuts
+            .iter()
+            .map(|shortcut| (shortcut.id(), shortcut.trigger_description())),
+    )
+}
+
+fn new_shortcut(action: HotkeyAction) -> NewShortcut {
+    NewShortcut::new(action.id(), action.description())
+        .preferred_trigger(action.preferred_trigger())
+}
@@ -0,0 +1,252 @@
+use super::*;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use x11rb::CURRENT_TIME;
+use x11rb::connection::Connection;
+use x11rb::protocol::xproto::{KEY_PRESS_EVENT, KEY_RELEASE_EVENT, ModMask};
+use x11rb::protocol::xtest::ConnectionExt as _;
+use x11rb::wrapper::ConnectionExt as _;
+
+#[test]
+fn action_ids_are_unique() {
+    for action in HotkeyAction::ALL {
+        assert_eq!(
+            Some(action),
+            HotkeyAction::from_id(action.id()),
+            "Should find {action:?} by its id"
+        );
+    }
+    assert_eq!(None, HotkeyAction::from_id("unknown"));
+}
+
+#[test]
+fn toggle_keeps_original_id() {
+    assert_eq!(
+        Some(HotkeyAction::Toggle),
+        HotkeyAction::from_id("Turbo Clicker Trigger"),
+        "Existing bindings should still toggle the autoclicker"
+    );
+}
+
+#[test]
+fn describe_bindings_marks_missing_triggers() {
+    let bindings = describe_bindings([
+        ("Turbo Clicker Trigger", "Ctrl+Shift+F12"),
+        ("Turbo Clicker Start", ""),
+    ]);
+
+    assert_eq!(
+        HotkeyAction::ALL.len(),
+        bindings.len(),
+        "Should describe every action"
+    );
+    assert_eq!("Start/stop the autoclicker: Ctrl+Shift+F12", bindings[0]);
+    assert_eq!(
+        "Start the autoclicker: not bound", bindings[1],
+        "Empty trigger should be not bound"
+    );
+    assert_eq!(
+        "Stop the autoclicker: not bound", bindings[2],
+        "Missing shortcut should be not bound"
+    );
+}
+
+#[test]
+fn x11_fallback_only_on_x11_sessions() {
+    assert!(x11_session(Some("x11"), false));
+    assert!(x11_session(Some("x11"), true));
+    assert!(!x11_session(Some("wayland"), false));
+    assert!(!x11_session(None, true));
+    assert!(x11_session(None, false));
+    assert!(!x11_session(Some("tty"), true));
+}
+
+#[test]
+fn portal_backoff_doubles_up_to_a_minute() {
+    let delays: Vec<u64> = (1..=8)
+        .map(|attempt| portal::backoff(attempt).as_secs())
+        .collect();
+    assert_eq!(vec![1, 2, 4, 8, 16, 32, 60, 60], delays);
+    assert_eq!(
+        Duration::from_secs(60),
+        portal::backoff(u32::MAX),
+        "Should not overflow"
+    );
+}
+
+#[test]
+fn x11_parse_trigger() {
+    let (modifiers, keysym) = x11::parse_trigger("CTRL+SHIFT+F12").expect("Should parse trigger");
+    assert_eq!(ModMask::CONTROL | ModMask::SHIFT, modifiers);
+    assert_eq!(0xffc9, keysym, "Should be the keysym of F12");
+
+    let (modifiers, keysym) = x11::parse_trigger("alt + Page_Up").expect("Should parse trigger");
+    assert_eq!(
+        ModMask::M1,
+        modifiers,
+        "Modifiers should be case insensitive"
+    );
+    assert_eq!(0xff55, keysym, "Should be the keysym of Page_Up");
+
+    let (modifiers, keysym) = x11::parse_trigger("A").expect("Should parse trigger");
+    assert_eq!(ModMask::from(0u16), modifiers, "Should have no modifiers");
+    assert_eq!(
+        u32::from('a'),
+        keysym,
+        "Letters should use the lowercase keysym"
+    );
+
+    assert!(
+        x11::parse_trigger("CTRL+").is_err(),
+        "Missing key should fail"
+    );
+    assert!(
+        x11::parse_trigger("HYPER+A").is_err(),
+        "Unknown modifier should fail"
+    );
+    assert!(
+        x11::parse_trigger("CTRL+F36").is_err(),
+        "Unknown key should fail"
+    );
+}
+
+#[test]
+fn evdev_parse_trigger() {
+    let trigger = evdev_backend::parse_trigger("CTRL+SHIFT+F12").expect("Should parse trigger");
+    assert_eq!(
+        evdev_backend::parse_trigger("shift+ctrl+KEY_F12").expect("Should parse trigger"),
+        trigger,
+        "Evdev names and modifiers in any order should give the same trigger"
+    );
+    assert_eq!(
+        evdev_backend::parse_trigger("CTRL+SHIFT+KEY_PAGEUP"),
+        evdev_backend::parse_trigger("ctrl+shift+Page_Up"),
+        "X11 names should map to the evdev key"
+    );
+    assert!(
+        evdev_backend::parse_trigger("BTN_SIDE").is_ok(),
+        "Mouse buttons should be valid triggers"
+    );
+    assert!(
+        evdev_backend::parse_trigger("CTRL+").is_err(),
+        "Missing key should fail"
+    );
+    assert!(
+        evdev_backend::parse_trigger("CTRL+NOT_A_KEY").is_err(),
+        "Unknown key should fail"
+    );
+}
+
+#[test]
+fn mouse_triggers_are_evdev_buttons() {
+    for trigger in [
+        MouseTrigger::Back,
+        MouseTrigger::Forward,
+        MouseTrigger::Middle,
+    ] {
+        let name = trigger.key_name().expect("Should have a button");
+        assert!(
+            evdev_backend::parse_trigger(name).is_ok(),
+            "{trigger:?} should be a valid evdev trigger"
+        );
+        assert!(trigger.button().is_some());
+    }
+    assert_eq!(None, MouseTrigger::Off.button());
+    let (events, _receiver) = mpsc::unbounded_channel();
+    assert!(
+        MouseTrigger::Off.listen(events).is_ok(),
+        "Off should not listen to anything"
+    );
+}
+
+#[test]
+fn evdev_matcher_tracks_modifiers() {
+    use evdev::KeyCode;
+
+    let trigger = evdev_backend::parse_trigger("CTRL+SHIFT+F12").unwrap();
+    let side = evdev_backend::parse_trigger("BTN_SIDE").unwrap();
+    let mut matcher = evdev_backend::Matcher::new(vec![
+        (trigger, HotkeyAction::Toggle),
+        (side, HotkeyAction::Start),
+    ]);
+
+    assert_eq!(
+        None,
+        matcher.handle(KeyCode::KEY_F12, 1),
+        "Key without modifiers should not match"
+    );
+    assert_eq!(None, matcher.handle(KeyCode::KEY_F12, 0));
+
+    assert_eq!(None, matcher.handle(KeyCode::KEY_LEFTCTRL, 1));
+    assert_eq!(None, matcher.handle(KeyCode::KEY_RIGHTSHIFT, 1));
+    assert_eq!(
+        Some(HotkeyEvent::Pressed(HotkeyAction::Toggle)),
+        matcher.handle(KeyCode::KEY_F12, 1)
+    );
+    assert_eq!(
+        None,
+        matcher.handle(KeyCode::KEY_F12, 2),
+        "Repeats should be ignored"
+    );
+    assert_eq!(
+        None,
+        matcher.handle(KeyCode::BTN_SIDE, 1),
+        "Extra modifiers should not match"
+    );
+    assert_eq!(None, matcher.handle(KeyCode::KEY_LEFTCTRL, 0));
+    assert_eq!(
+        Some(HotkeyEvent::Released(HotkeyAction::Toggle)),
+        matcher.handle(KeyCode::KEY_F12, 0),
+        "Release should match after the modifiers are released"
+    );
+
+    assert_eq!(None, matcher.handle(KeyCode::KEY_RIGHTSHIFT, 0));
+    assert_eq!(
+        Some(HotkeyEvent::Pressed(HotkeyAction::Start)),
+        matcher.handle(KeyCode::BTN_SIDE, 1)
+    );
+    assert_eq!(
+        Some(HotkeyEvent::Released(HotkeyAction::Start)),
+        matcher.handle(KeyCode::BTN_SIDE, 0)
+    );
+}
+
+#[tokio::test]
+async fn x11_hotkeys_send_key_events() {
+    let (events, mut receiver) = mpsc::unbounded_channel();
+    let hotkeys = X11Hotkeys::grab("CTRL+SHIFT+F12", events).expect("Should grab hotkeys");
+    assert_eq!(
+        "Start/stop the autoclicker: CTRL+SHIFT+F12",
+        hotkeys.bindings()[0]
+    );
+
+    // Simulate the key presses with XTEST from a second client.
+    let (conn, screen_num) = x11rb::connect(None).expect("Should connect to X server");
+    let root = conn.setup().roots[screen_num].root;
+    let keys = [0xffe3, 0xffe1, 0xffc9].map(|keysym| {
+        x11::keycode_for(&conn, keysym)
+            .expect("Should read keyboard mapping")
+            .expect("Should have a key for the keysym")
+    });
+    for (event_type, keys) in [
+        (KEY_PRESS_EVENT, keys),
+        (KEY_RELEASE_EVENT, [keys[2], keys[1], keys[0]]),
+    ] {
+        for key in keys {
+            conn.xtest_fake_input(event_type, key, CURRENT_TIME, root, 0, 0, 0)
+                .expect("Should send fake input");
+        }
+    }
+    conn.sync().expect("Should sync with X server");
+
+    for expected in [
+        HotkeyEvent::Pressed(HotkeyAction::Toggle),
+        HotkeyEvent::Released(HotkeyAction::Toggle),
+    ] {
+        let event = timeout(Duration::from_secs(1), receiver.recv())
+            .await
+            .expect("Should receive event in time");
+        assert_eq!(Some(expected), event);
+    }
+}
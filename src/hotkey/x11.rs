@@ -0,0 +1,271 @@
+use super::{HotkeyAction, HotkeyEvent, Modifier, describe_bindings, split_trigger, trigger_for};
+use crate::input::{POLL_TIMEOUT, wait_readable};
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use tokio::sync::mpsc::UnboundedSender;
+use x11rb::connection::{Connection, RequestConnection};
+use x11rb::cookie::VoidCookie;
+use x11rb::errors::ConnectionError;
+use x11rb::protocol::Event;
+use x11rb::protocol::xkb::{self, ConnectionExt as _};
+use x11rb::protocol::xproto::{ConnectionExt as _, Grab, GrabMode, Keycode, ModMask, Window};
+use x11rb::rust_connection::RustConnection;
+
+/// Global hotkeys grabbed from the X server, for X11 sessions without the GlobalShortcuts portal.
+#[derive(Clone)]
+pub struct X11Hotkeys {
+    bindings: Vec<String>,
+    conn: Arc<RustConnection>,
+    root: Window,
+    /// Set once the grabs are released, the listening thread ends then.
+    closed: Arc<AtomicBool>,
+}
+
+impl X11Hotkeys {
+    /// Grab the trigger of every action from the X server and send their events to `events`.
+    /// The toggle action uses `toggle_trigger`, or its default if it is empty.
+    /// Returns an error if there is no X server or none of the triggers could be grabbed.
+    pub fn grab(
+        toggle_trigger: &str,
+        events: UnboundedSender<HotkeyEvent>,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let (conn, screen_num) = x11rb::connect(None)?;
+        let root = conn.setup().roots[screen_num].root;
+
+        // Otherwise holding a key sends a release before every repeated press.
+        conn.xkb_use_extension(1, 0)?.reply()?;
+        let flag = xkb::PerClientFlag::DETECTABLE_AUTO_REPEAT;
+        conn.xkb_per_client_flags(
+            xkb::ID::USE_CORE_KBD.into(),
+            flag,
+            flag,
+            0u32.into(),
+            0u32.into(),
+            0u32.into(),
+        )?
+        .reply()?;
+
+        let mut grabs = HashMap::new();
+        let mut bound = Vec::new();
+        for action in HotkeyAction::ALL {
+            let trigger = trigger_for(action, toggle_trigger);
+            match grab_trigger(&conn, root, trigger) {
+                Ok(key) => {
+                    grabs.insert(key, action);
+                    bound.push((action.id(), trigger));
+                }
+                Err(e) => eprintln!("Failed to grab {trigger} for {action:?}: {e}"),
+            }
+        }
+        if grabs.is_empty() {
+            return Err("none of the hotkeys could be grabbed".into());
+        }
+
+        let conn = Arc::new(conn);
+        let closed = Arc::new(AtomicBool::new(false));
+        {
+            let conn = Arc::clone(&conn);
+            let closed = Arc::clone(&closed);
+            thread::spawn(move || listen(&conn, grabs, events, &closed));
+        }
+        Ok(Self {
+            bindings: describe_bindings(bound),
+            conn,
+            root,
+            closed,
+        })
+    }
+
+    /// Release the grabbed keys and stop listening for them.
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+        if let Err(e) = checked(self.conn.ungrab_key(Grab::ANY, self.root, ModMask::ANY)) {
+            eprintln!("Failed to release the X11 hotkeys: {e}");
+        }
+    }
+
+    /// Return a description of the trigger bound to each hotkey.
+    pub fn bindings(&self) -> Vec<String> {
+        self.bindings.clone()
+    }
+}
+
+/// Forward the events of the grabbed keys until the receiver is gone, the connection is lost
+/// or the hotkeys are closed.
+fn listen(
+    conn: &RustConnection,
+    grabs: HashMap<(Keycode, u16), HotkeyAction>,
+    events: UnboundedSender<HotkeyEvent>,
+    closed: &AtomicBool,
+) {
+    let relevant = u16::from(ModMask::CONTROL | ModMask::SHIFT | ModMask::M1 | ModMask::M4);
+    let mut held = HashMap::new();
+    while !closed.load(Ordering::Acquire) {
+        let event = match conn.poll_for_event() {
+            Ok(Some(event)) => event,
+            // Waiting with a timeout, so closing the hotkeys is noticed.
+            Ok(None) => match wait_readable(conn.stream(), POLL_TIMEOUT) {
+                Ok(_) => continue,
+                Err(e) => {
+                    eprintln!("Failed to wait for the X server, X11 hotkeys stopped: {e}");
+                    return;
+                }
+            },
+            Err(e) => {
+                eprintln!("Lost connection to the X server, X11 hotkeys stopped: {e}");
+                return;
+            }
+        };
+        let hotkey_event = match event {
+            Event::KeyPress(key) => {
+                let Some(&action) = grabs.get(&(key.detail, u16::from(key.state) & relevant))
+                else {
+                    continue;
+                };
+                // Holding the keys repeats the press, only the first one counts.
+                if held.insert(key.detail, action).is_some() {
+                    continue;
+                }
+                HotkeyEvent::Pressed(action)
+            }
+            // The modifiers might already be released, so only the key identifies the hotkey.
+            Event::KeyRelease(key) => match held.remove(&key.detail) {
+                Some(action) => HotkeyEvent::Released(action),
+                None => continue,
+            },
+            _ => continue,
+        };
+        if events.send(hotkey_event).is_err() {
+            return;
+        }
+    }
+}
+
+/// Grab the key combination of the trigger on the root window.
+/// Returns the keycode and modifiers the key events will have.
+fn grab_trigger(
+    conn: &RustConnection,
+    root: Window,
+    trigger: &str,
+) -> Result<(Keycode, u16), Box<dyn Error + Send + Sync>> {
+    let (modifiers, keysym) = parse_trigger(trigger)?;
+    let keycode = keycode_for(conn, keysym)?.ok_or("no key on the keyboard produces it")?;
+    // Caps Lock and Num Lock are separate modifiers, the hotkey should work regardless of them.
+    let variants = [
+        ModMask::from(0u16),
+        ModMask::LOCK,
+        ModMask::M2,
+        ModMask::LOCK | ModMask::M2,
+    ];
+    for (grabbed, ignored) in variants.into_iter().enumerate() {
+        let res = checked(conn.grab_key(
+            false,
+            root,
+            modifiers | ignored,
+            keycode,
+            GrabMode::ASYNC,
+            GrabMode::ASYNC,
+        ));
+        if let Err(e) = res {
+            // A trigger that only works with some lock keys would be confusing.
+            for ignored in &variants[..grabbed] {
+                if let Err(ungrab_err) =
+                    checked(conn.ungrab_key(keycode, root, modifiers | *ignored))
+                {
+                    eprintln!("Failed to release {trigger}: {ungrab_err}");
+                }
+            }
+            return Err(e.into());
+        }
+    }
+    Ok((keycode, u16::from(modifiers)))
+}
+
+/// Wait until the X server has handled the request and return its error.
+fn checked<C>(cookie: Result<VoidCookie<'_, C>, ConnectionError>) -> Result<(), String>
+where
+    C: RequestConnection,
+{
+    cookie
+        .map_err(|e| e.to_string())?
+        .check()
+        .map_err(|e| e.to_string())
+}
+
+/// Find the keycode of the key that produces the given keysym.
+pub(super) fn keycode_for(
+    conn: &impl Connection,
+    keysym: u32,
+) -> Result<Option<Keycode>, Box<dyn Error + Send + Sync>> {
+    let setup = conn.setup();
+    let count = setup.max_keycode - setup.min_keycode + 1;
+    let mapping = conn
+        .get_keyboard_mapping(setup.min_keycode, count)?
+        .reply()?;
+    let keysyms_per_keycode = usize::from(mapping.keysyms_per_keycode);
+    Ok(mapping
+        .keysyms
+        .chunks(keysyms_per_keycode)
+        .position(|keysyms| keysyms.contains(&keysym))
+        .map(|i| setup.min_keycode + i as u8))
+}
+
+/// Parse a trigger like "CTRL+SHIFT+F12" into its modifiers and keysym.
+pub(super) fn parse_trigger(trigger: &str) -> Result<(ModMask, u32), String> {
+    let (modifiers, key) = split_trigger(trigger)?;
+    let modifiers = modifiers
+        .into_iter()
+        .fold(ModMask::from(0u16), |mask, modifier| {
+            mask | match modifier {
+                Modifier::Ctrl => ModMask::CONTROL,
+                Modifier::Shift => ModMask::SHIFT,
+                Modifier::Alt => ModMask::M1,
+                Modifier::Logo => ModMask::M4,
+            }
+        });
+    let keysym =
+        keysym_from_name(key).ok_or_else(|| format!("unknown key \"{key}\" in \"{trigger}\""))?;
+    Ok((modifiers, keysym))
+}
+
+/// Look up the keysym of a key name, covering the keys that make sense for a hotkey.
+fn keysym_from_name(name: &str) -> Option<u32> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next())
+        && c.is_ascii_alphanumeric()
+    {
+        return Some(u32::from(c.to_ascii_lowercase()));
+    }
+    if let Some(n) = name
+        .strip_prefix(['F', 'f'])
+        .and_then(|n| n.parse::<u32>().ok())
+        && (1..=35).contains(&n)
+    {
+        return Some(0xffbe + n - 1);
+    }
+    let keysym = match name.to_lowercase().as_str() {
+        "space" => 0x0020,
+        "backspace" => 0xff08,
+        "tab" => 0xff09,
+        "return" => 0xff0d,
+        "pause" => 0xff13,
+        "scroll_lock" => 0xff14,
+        "escape" => 0xff1b,
+        "home" => 0xff50,
+        "left" => 0xff51,
+        "up" => 0xff52,
+        "right" => 0xff53,
+        "down" => 0xff54,
+        "page_up" => 0xff55,
+        "page_down" => 0xff56,
+        "end" => 0xff57,
+        "print" => 0xff61,
+        "insert" => 0xff63,
+        "delete" => 0xffff,
+        _ => return None,
+    };
+    Some(keysym)
+}
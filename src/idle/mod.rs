@@ -0,0 +1,110 @@
+use crate::input::{DeviceReader, open_devices};
+use ashpd::zbus;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+#[cfg(test)]
+mod test;
+
+const MUTTER_IDLE_SERVICE: &str = "org.gnome.Mutter.IdleMonitor";
+const MUTTER_IDLE_PATH: &str = "/org/gnome/Mutter/IdleMonitor/Core";
+
+/// The ways to detect that the user is inactive the user can choose from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum IdleBackendKind {
+    /// Use the idle monitor of GNOME, fall back to evdev if it is not available.
+    #[default]
+    Auto,
+    /// Ask the idle monitor of GNOME (Mutter) over D-Bus.
+    Mutter,
+    /// Read the input devices directly, works on any compositor.
+    Evdev,
+}
+
+/// What the keep-alive mode does once the user is inactive.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum KeepaliveAction {
+    /// Move the pointer by one pixel and back.
+    #[default]
+    Nudge,
+    /// Click the left button.
+    Click,
+}
+
+/// Reports how long the user has been inactive.
+pub enum IdleMonitor {
+    Mutter(zbus::Connection),
+    /// The time of the last input read from the input devices.
+    /// Reading stops once the monitor is dropped.
+    Evdev {
+        last_input: Arc<Mutex<Instant>>,
+        _reader: DeviceReader,
+    },
+}
+
+impl IdleMonitor {
+    /// Connect to the idle monitor of the given kind.
+    pub async fn new(kind: IdleBackendKind) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        match kind {
+            IdleBackendKind::Auto => match Self::connect_mutter().await {
+                Ok(monitor) => Ok(monitor),
+                Err(e) => {
+                    eprintln!("Mutter idle monitor is not available, falling back to evdev: {e}");
+                    Self::listen_evdev().map_err(|evdev_err| {
+                        format!("{e} (evdev fallback failed: {evdev_err})").into()
+                    })
+                }
+            },
+            IdleBackendKind::Mutter => Self::connect_mutter().await,
+            IdleBackendKind::Evdev => Self::listen_evdev(),
+        }
+    }
+
+    async fn connect_mutter() -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let monitor = IdleMonitor::Mutter(zbus::Connection::session().await?);
+        // Fail early if the compositor has no idle monitor.
+        monitor.idle_time().await?;
+        Ok(monitor)
+    }
+
+    /// Start reading every input device, our own virtual device does not count as user input.
+    fn listen_evdev() -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let devices = open_devices(|_| true, "no input device can be read")?;
+
+        let last_input = Arc::new(Mutex::new(Instant::now()));
+        let input = Arc::clone(&last_input);
+        let reader = DeviceReader::spawn(devices, move |_| {
+            *input.lock().unwrap() = Instant::now();
+            true
+        });
+        Ok(IdleMonitor::Evdev {
+            last_input,
+            _reader: reader,
+        })
+    }
+
+    /// The time since the last input of the user.
+    pub async fn idle_time(&self) -> Result<Duration, Box<dyn Error + Send + Sync>> {
+        match self {
+            IdleMonitor::Mutter(connection) => {
+                let reply = connection
+                    .call_method(
+                        Some(MUTTER_IDLE_SERVICE),
+                        MUTTER_IDLE_PATH,
+                        Some(MUTTER_IDLE_SERVICE),
+                        "GetIdletime",
+                        &(),
+                    )
+                    .await?;
+                let idle_ms: u64 = reply.body().deserialize()?;
+                Ok(Duration::from_millis(idle_ms))
+            }
+            IdleMonitor::Evdev { last_input, .. } => Ok(last_input.lock().unwrap().elapsed()),
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use super::*;
+
+#[tokio::test]
+async fn evdev_idle_time_counts_from_last_input() {
+    let last_input = Arc::new(Mutex::new(Instant::now() - Duration::from_secs(90)));
+    let monitor = IdleMonitor::Evdev {
+        last_input: Arc::clone(&last_input),
+        _reader: DeviceReader::spawn(Vec::new(), |_| true),
+    };
+
+    let idle = monitor.idle_time().await.expect("Should get idle time");
+    assert!(idle >= Duration::from_secs(90), "Got {idle:?}");
+
+    *last_input.lock().unwrap() = Instant::now();
+    let idle = monitor.idle_time().await.expect("Should get idle time");
+    assert!(
+        idle < Duration::from_secs(1),
+        "Input should reset idle time"
+    );
+}
+
+#[test]
+fn settings_use_lowercase_names() {
+    assert_eq!(
+        "\"mutter\"",
+        serde_json::to_string(&IdleBackendKind::Mutter).unwrap()
+    );
+    assert_eq!(
+        KeepaliveAction::Click,
+        serde_json::from_str("\"click\"").unwrap()
+    );
+}
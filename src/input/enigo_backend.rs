@@ -0,0 +1,49 @@
+use super::{InputBackend, InputResult};
+use enigo::{Button, Coordinate, Direction, Enigo, Keyboard, Mouse, Settings};
+
+/// Offset between evdev key codes and the XKB key codes enigo expects.
+const XKB_KEYCODE_OFFSET: u16 = 8;
+
+/// Input backend using enigo, which supports X11 and Wayland.
+pub struct EnigoBackend {
+    enigo: Enigo,
+}
+
+impl EnigoBackend {
+    /// Create a new enigo instance for virtual input.
+    /// Returns an error if there is no display or the permission to simulate input was denied.
+    pub fn new() -> InputResult<Self> {
+        let mut enigo = Enigo::new(&Settings::default())?;
+
+        // Move the mouse slightly to ensure the permission prompt is triggered.
+        enigo.move_mouse(0, 0, Coordinate::Rel)?;
+
+        Ok(Self { enigo })
+    }
+}
+
+impl InputBackend for EnigoBackend {
+    fn button(&mut self, button: Button, direction: Direction) -> InputResult<()> {
+        Ok(self.enigo.button(button, direction)?)
+    }
+
+    fn move_by(&mut self, dx: i32, dy: i32) -> InputResult<()> {
+        Ok(self.enigo.move_mouse(dx, dy, Coordinate::Rel)?)
+    }
+
+    fn move_to(&mut self, x: i32, y: i32) -> InputResult<()> {
+        Ok(self.enigo.move_mouse(x, y, Coordinate::Abs)?)
+    }
+
+    fn key(&mut self, code: u16, direction: Direction) -> InputResult<()> {
+        Ok(self.enigo.raw(code + XKB_KEYCODE_OFFSET, direction)?)
+    }
+
+    fn location(&self) -> InputResult<(i32, i32)> {
+        Ok(self.enigo.location()?)
+    }
+
+    fn screen_size(&self) -> InputResult<(i32, i32)> {
+        Ok(self.enigo.main_display()?)
+    }
+}
@@ -0,0 +1,113 @@
+use enigo::{Button, Direction, InputError, NewConError};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::io;
+
+mod enigo_backend;
+mod reader;
+#[cfg(test)]
+pub mod recording;
+mod uinput;
+
+#[cfg(test)]
+mod test;
+
+pub use enigo_backend::EnigoBackend;
+pub use reader::{DeviceReader, POLL_TIMEOUT, open_devices, wait_readable};
+pub use uinput::{DEVICE_NAME, UinputBackend, button_from_key};
+
+/// Result type returned by input backends.
+pub type InputResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+/// A backend that injects virtual input events.
+pub trait InputBackend: Send {
+    /// Press, release or click the given mouse button.
+    fn button(&mut self, button: Button, direction: Direction) -> InputResult<()>;
+
+    /// Move the pointer relative to its current position.
+    fn move_by(&mut self, dx: i32, dy: i32) -> InputResult<()>;
+
+    /// Move the pointer to the given position on the screen.
+    /// Backends that can't move to a position directly move relative to the current one.
+    fn move_to(&mut self, x: i32, y: i32) -> InputResult<()> {
+        let (current_x, current_y) = self.location()?;
+        self.move_by(x - current_x, y - current_y)
+    }
+
+    /// Press, release or click the key with the given evdev key code, independent of the layout.
+    fn key(&mut self, code: u16, direction: Direction) -> InputResult<()>;
+
+    /// Return the current position of the pointer.
+    fn location(&self) -> InputResult<(i32, i32)>;
+
+    /// Return the width and height of the main screen.
+    fn screen_size(&self) -> InputResult<(i32, i32)>;
+}
+
+/// The input backends the user can choose from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    /// Use enigo, fall back to uinput if enigo can't be initialized.
+    #[default]
+    Enigo,
+    /// Always use uinput.
+    Uinput,
+}
+
+/// Create the input backend of the given kind.
+pub fn create_backend(kind: BackendKind) -> InputResult<Box<dyn InputBackend>> {
+    match kind {
+        BackendKind::Enigo => match EnigoBackend::new() {
+            Ok(backend) => Ok(Box::new(backend)),
+            Err(e) => {
+                eprintln!("Failed to initialize enigo, falling back to uinput: {e}");
+                match UinputBackend::new() {
+                    Ok(backend) => Ok(Box::new(backend)),
+                    Err(uinput_err) => Err(format!(
+                        "{}\n{}",
+                        explain_error(e.as_ref()),
+                        explain_error(uinput_err.as_ref())
+                    )
+                    .into()),
+                }
+            }
+        },
+        BackendKind::Uinput => match UinputBackend::new() {
+            Ok(backend) => Ok(Box::new(backend)),
+            Err(e) => Err(explain_error(e.as_ref()).into()),
+        },
+    }
+}
+
+/// Turn a backend initialization error into a message that tells the user how to fix it.
+pub fn explain_error(e: &(dyn Error + 'static)) -> String {
+    if let Some(e) = e.downcast_ref::<NewConError>() {
+        return match e {
+            NewConError::EstablishCon(_) => format!(
+                "No display found, virtual input needs a running X11 or Wayland session ({e})."
+            ),
+            NewConError::NoPermission => "Permission to control input was denied. \
+                Retry and allow remote interaction when asked."
+                .to_string(),
+            _ => format!("Failed to connect to the display server: {e}."),
+        };
+    }
+    if e.downcast_ref::<InputError>().is_some() {
+        return format!(
+            "Simulating input failed, the permission to control input was probably denied ({e})."
+        );
+    }
+    if let Some(e) = e.downcast_ref::<io::Error>() {
+        return match e.kind() {
+            io::ErrorKind::PermissionDenied => "Permission to access /dev/uinput was denied. \
+                Add your user to the input group or install a udev rule for uinput."
+                .to_string(),
+            io::ErrorKind::NotFound => {
+                "/dev/uinput does not exist, load the uinput kernel module.".to_string()
+            }
+            _ => format!("Failed to create the uinput device: {e}."),
+        };
+    }
+    e.to_string()
+}
@@ -0,0 +1,121 @@
+use super::DEVICE_NAME;
+use evdev::{AttributeSetRef, Device, InputEvent, KeyCode};
+use rustix::event::{PollFd, PollFlags, Timespec, poll};
+use std::error::Error;
+use std::io;
+use std::os::fd::AsFd;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// How long the reading threads wait for events before checking if they were stopped.
+pub const POLL_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Open every readable input device whose keys are accepted by `has_keys`.
+/// Our own virtual device is left out, so clicks, macros and scripts are never read back as user input.
+/// Returns an error starting with `missing` if there is no such device.
+pub fn open_devices<F>(
+    has_keys: F,
+    missing: &str,
+) -> Result<Vec<Device>, Box<dyn Error + Send + Sync>>
+where
+    F: Fn(&AttributeSetRef<KeyCode>) -> bool,
+{
+    let devices: Vec<Device> = evdev::enumerate()
+        .map(|(_, device)| device)
+        .filter(|device| device.name() != Some(DEVICE_NAME))
+        .filter(|device| device.supported_keys().is_some_and(&has_keys))
+        .collect();
+    if devices.is_empty() {
+        return Err(
+            format!("{missing}, reading /dev/input requires being in the input group").into(),
+        );
+    }
+    Ok(devices)
+}
+
+/// Reads input devices on background threads until it is stopped.
+/// Dropping it stops the threads as well, but does not wait for them.
+pub struct DeviceReader {
+    stopped: Arc<AtomicBool>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl DeviceReader {
+    /// Pass every event of the devices to a clone of `on_event`, each device is read on its own thread.
+    /// A thread ends once `on_event` returns false, its device is removed or the reader is stopped.
+    pub fn spawn<F>(devices: Vec<Device>, on_event: F) -> Self
+    where
+        F: FnMut(InputEvent) -> bool + Clone + Send + 'static,
+    {
+        let stopped = Arc::new(AtomicBool::new(false));
+        let threads = devices
+            .into_iter()
+            .map(|device| {
+                let stopped = Arc::clone(&stopped);
+                let on_event = on_event.clone();
+                thread::spawn(move || read_device(device, &stopped, on_event))
+            })
+            .collect();
+        Self { stopped, threads }
+    }
+
+    /// Stop reading and wait until every thread has ended, which closes the devices.
+    pub fn stop(mut self) {
+        self.stopped.store(true, Ordering::Release);
+        for thread in self.threads.drain(..) {
+            if thread.join().is_err() {
+                eprintln!("A thread reading an input device panicked");
+            }
+        }
+    }
+}
+
+impl Drop for DeviceReader {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Release);
+    }
+}
+
+/// Pass the events of the device to `on_event` until it returns false, the device is removed or reading is stopped.
+fn read_device<F>(mut device: Device, stopped: &AtomicBool, mut on_event: F)
+where
+    F: FnMut(InputEvent) -> bool,
+{
+    let name = device.name().unwrap_or("unknown device").to_string();
+    while !stopped.load(Ordering::Acquire) {
+        match wait_readable(&device, POLL_TIMEOUT) {
+            Ok(true) => (),
+            Ok(false) => continue,
+            Err(e) => {
+                eprintln!("Stopped reading {name}: {e}");
+                return;
+            }
+        }
+        let fetched: Vec<InputEvent> = match device.fetch_events() {
+            Ok(fetched) => fetched.collect(),
+            Err(e) => {
+                eprintln!("Stopped reading {name}: {e}");
+                return;
+            }
+        };
+        for event in fetched {
+            if !on_event(event) {
+                return;
+            }
+        }
+    }
+}
+
+/// Wait until the file can be read without blocking.
+/// Returns false if it can't be read within the timeout.
+pub fn wait_readable(fd: impl AsFd, timeout: Duration) -> io::Result<bool> {
+    let mut fds = [PollFd::new(&fd, PollFlags::IN)];
+    let timeout = Timespec::try_from(timeout).map_err(io::Error::other)?;
+    match poll(&mut fds, Some(&timeout)) {
+        Ok(ready) => Ok(ready > 0),
+        Err(rustix::io::Errno::INTR) => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
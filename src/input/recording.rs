@@ -0,0 +1,90 @@
+use super::{InputBackend, InputResult};
+use enigo::{Button, Direction};
+use std::sync::{Arc, Mutex};
+use tokio::time::Instant;
+
+/// An input event as received by the RecordingBackend.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputEvent {
+    Button(Button, Direction),
+    Move(i32, i32),
+    Key(u16, Direction),
+}
+
+/// The screen size reported by the RecordingBackend.
+pub const SCREEN_SIZE: (i32, i32) = (1920, 1080);
+
+/// In-memory input backend for tests.
+/// Records all events with the time they were received, clones share the same recording.
+/// The pointer does not move with the events, it stays where `set_location` put it.
+#[derive(Clone, Default)]
+pub struct RecordingBackend {
+    events: Arc<Mutex<Vec<(Instant, InputEvent)>>>,
+    location: Arc<Mutex<(i32, i32)>>,
+    failing_key: Arc<Mutex<Option<u16>>>,
+}
+
+impl RecordingBackend {
+    /// Create a new empty recording.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the times at which the given button was clicked.
+    pub fn clicks(&self, button: Button) -> Vec<Instant> {
+        let events = self.events.lock().unwrap();
+        events
+            .iter()
+            .filter(|(_, event)| *event == InputEvent::Button(button, Direction::Click))
+            .map(|(time, _)| *time)
+            .collect()
+    }
+
+    /// Return all recorded events in the order they were received.
+    pub fn events(&self) -> Vec<InputEvent> {
+        let events = self.events.lock().unwrap();
+        events.iter().map(|(_, event)| *event).collect()
+    }
+
+    /// Put the pointer at the given position, e.g. as if the user moved it.
+    pub fn set_location(&self, x: i32, y: i32) {
+        *self.location.lock().unwrap() = (x, y);
+    }
+
+    /// Make pressing the key fail, e.g. as if the device had been unplugged.
+    pub fn fail_key(&self, code: u16) {
+        *self.failing_key.lock().unwrap() = Some(code);
+    }
+
+    fn record(&self, event: InputEvent) {
+        self.events.lock().unwrap().push((Instant::now(), event));
+    }
+}
+
+impl InputBackend for RecordingBackend {
+    fn button(&mut self, button: Button, direction: Direction) -> InputResult<()> {
+        self.record(InputEvent::Button(button, direction));
+        Ok(())
+    }
+
+    fn move_by(&mut self, dx: i32, dy: i32) -> InputResult<()> {
+        self.record(InputEvent::Move(dx, dy));
+        Ok(())
+    }
+
+    fn key(&mut self, code: u16, direction: Direction) -> InputResult<()> {
+        if direction == Direction::Press && *self.failing_key.lock().unwrap() == Some(code) {
+            return Err(format!("failed to press key {code}").into());
+        }
+        self.record(InputEvent::Key(code, direction));
+        Ok(())
+    }
+
+    fn location(&self) -> InputResult<(i32, i32)> {
+        Ok(*self.location.lock().unwrap())
+    }
+
+    fn screen_size(&self) -> InputResult<(i32, i32)> {
+        Ok(SCREEN_SIZE)
+    }
+}
@@ -0,0 +1,135 @@
+use super::uinput::{DEVICE_NAME, button_events};
+use super::*;
+use evdev::{Device, EventType, KeyCode, RelativeAxisCode};
+use std::thread::sleep;
+use std::time::Duration;
+
+#[test]
+fn uinput_button_events() {
+    let batches = button_events(Button::Left, Direction::Click);
+    assert_eq!(2, batches.len(), "Click should be press and release");
+    assert_eq!(EventType::KEY, batches[0][0].event_type());
+    assert_eq!(KeyCode::BTN_LEFT.code(), batches[0][0].code());
+    assert_eq!(1, batches[0][0].value(), "First should be press");
+    assert_eq!(0, batches[1][0].value(), "Second should be release");
+
+    let batches = button_events(Button::Back, Direction::Press);
+    assert_eq!(1, batches.len(), "Press should be a single batch");
+    assert_eq!(KeyCode::BTN_SIDE.code(), batches[0][0].code());
+
+    let batches = button_events(Button::ScrollDown, Direction::Click);
+    assert_eq!(1, batches.len(), "Scroll should be a single batch");
+    assert_eq!(EventType::RELATIVE, batches[0][0].event_type());
+    assert_eq!(RelativeAxisCode::REL_WHEEL.0, batches[0][0].code());
+    assert_eq!(-1, batches[0][0].value(), "Scrolling down is negative");
+
+    let batches = button_events(Button::ScrollUp, Direction::Release);
+    assert!(batches.is_empty(), "Releasing scroll should do nothing");
+}
+
+#[test]
+fn button_from_key_is_inverse_of_button_events() {
+    for button in [
+        Button::Left,
+        Button::Right,
+        Button::Middle,
+        Button::Back,
+        Button::Forward,
+    ] {
+        let code = KeyCode::new(button_events(button, Direction::Press)[0][0].code());
+        assert_eq!(Some(button), button_from_key(code), "{button:?}");
+    }
+    assert_eq!(None, button_from_key(KeyCode::KEY_A));
+}
+
+#[test]
+fn explain_error_describes_fix() {
+    let e = io::Error::from(io::ErrorKind::PermissionDenied);
+    assert!(
+        explain_error(&e).contains("input group"),
+        "Should explain uinput permissions"
+    );
+
+    let e = io::Error::from(io::ErrorKind::NotFound);
+    assert!(
+        explain_error(&e).contains("uinput kernel module"),
+        "Should explain missing uinput"
+    );
+
+    let e = NewConError::EstablishCon("no display");
+    assert!(
+        explain_error(&e).starts_with("No display found"),
+        "Should explain missing display"
+    );
+
+    let e = NewConError::NoPermission;
+    assert!(
+        explain_error(&e).contains("denied"),
+        "Should explain denied permission"
+    );
+}
+
+#[test]
+#[ignore = "requires write access to /dev/uinput"]
+fn uinput_backend_emits_clicks() {
+    let mut backend = UinputBackend::new().expect("Should create uinput device");
+    let path = backend
+        .device
+        .enumerate_dev_nodes_blocking()
+        .expect("Should list device nodes")
+        .next()
+        .expect("Should have a device node")
+        .expect("Should read device node");
+
+    // The device node may take a moment to become available.
+    let mut reader = None;
+    for _ in 0..50 {
+        if let Ok(device) = Device::open(&path) {
+            reader = Some(device);
+            break;
+        }
+        sleep(Duration::from_millis(20));
+    }
+    let mut reader = reader.expect("Should open device node");
+    assert_eq!(Some(DEVICE_NAME), reader.name(), "Device name should match");
+
+    backend
+        .button(Button::Left, Direction::Click)
+        .expect("Should click");
+
+    let mut values = Vec::new();
+    while values.len() < 2 {
+        for event in reader.fetch_events().expect("Should read events") {
+            if event.event_type() == EventType::KEY && event.code() == KeyCode::BTN_LEFT.code() {
+                values.push(event.value());
+            }
+        }
+    }
+    assert_eq!(vec![1, 0], values, "Should press and release left button");
+}
+
+#[test]
+#[ignore = "requires write access to /dev/uinput"]
+fn uinput_backend_rejects_positions() {
+    let mut backend = UinputBackend::new().expect("Should create uinput device");
+    assert!(backend.location().is_err(), "Position should not be known");
+    assert!(
+        backend.move_to(100, 200).is_err(),
+        "Moving to a position should be rejected"
+    );
+}
+
+#[test]
+fn wait_readable_times_out_until_data_arrives() {
+    let (reader, mut writer) = std::io::pipe().expect("Should create pipe");
+    assert!(
+        !wait_readable(&reader, Duration::from_millis(10)).expect("Should wait"),
+        "Empty pipe should not be readable"
+    );
+
+    std::io::Write::write_all(&mut writer, b"x").expect("Should write to pipe");
+    assert!(
+        wait_readable(&reader, Duration::from_millis(10)).expect("Should wait"),
+        "Pipe with data should be readable"
+    );
+}
@@ -0,0 +1,133 @@
+use super::{InputBackend, InputResult};
+use enigo::{Button, Direction};
+use evdev::uinput::VirtualDevice;
+use evdev::{AttributeSet, InputEvent, KeyCode, KeyEvent, RelativeAxisCode, RelativeAxisEvent};
+
+/// Name of the virtual device, used to tell our own events apart from real input.
+pub const DEVICE_NAME: &str = "Turbo Clicker Virtual Input";
+
+/// Highest key code of a regular keyboard key, everything above is reserved for buttons.
+const MAX_KEYBOARD_KEY: u16 = 248;
+
+/// Input backend creating a virtual mouse and keyboard through /dev/uinput.
+/// Works independent of the display server, but requires write access to /dev/uinput,
+/// usually by being in the input group.
+pub struct UinputBackend {
+    pub(super) device: VirtualDevice,
+}
+
+impl UinputBackend {
+    /// Create the virtual device.
+    /// Returns an error if /dev/uinput can't be opened.
+    pub fn new() -> InputResult<Self> {
+        let mut keys: AttributeSet<KeyCode> = (1..=MAX_KEYBOARD_KEY).map(KeyCode::new).collect();
+        for button in [
+            KeyCode::BTN_LEFT,
+            KeyCode::BTN_RIGHT,
+            KeyCode::BTN_MIDDLE,
+            KeyCode::BTN_SIDE,
+            KeyCode::BTN_EXTRA,
+        ] {
+            keys.insert(button);
+        }
+        let axes = AttributeSet::from_iter([
+            RelativeAxisCode::REL_X,
+            RelativeAxisCode::REL_Y,
+            RelativeAxisCode::REL_WHEEL,
+            RelativeAxisCode::REL_HWHEEL,
+        ]);
+
+        let device = VirtualDevice::builder()?
+            .name(DEVICE_NAME)
+            .with_keys(&keys)?
+            .with_relative_axes(&axes)?
+            .build()?;
+        Ok(Self { device })
+    }
+
+    /// Send the given batches of events, each batch is followed by a SYN_REPORT.
+    fn emit(&mut self, batches: Vec<Vec<InputEvent>>) -> InputResult<()> {
+        for batch in batches {
+            self.device.emit(&batch)?;
+        }
+        Ok(())
+    }
+}
+
+impl InputBackend for UinputBackend {
+    fn button(&mut self, button: Button, direction: Direction) -> InputResult<()> {
+        self.emit(button_events(button, direction))
+    }
+
+    fn move_by(&mut self, dx: i32, dy: i32) -> InputResult<()> {
+        self.emit(vec![vec![
+            *RelativeAxisEvent::new(RelativeAxisCode::REL_X, dx),
+            *RelativeAxisEvent::new(RelativeAxisCode::REL_Y, dy),
+        ]])
+    }
+
+    fn move_to(&mut self, _x: i32, _y: i32) -> InputResult<()> {
+        Err("The uinput backend can't move the pointer to a position, only by an offset".into())
+    }
+
+    fn key(&mut self, code: u16, direction: Direction) -> InputResult<()> {
+        if code == 0 || code > MAX_KEYBOARD_KEY {
+            return Err(format!("Key code {code} is not a keyboard key").into());
+        }
+        self.emit(key_events(KeyCode::new(code), direction))
+    }
+
+    fn location(&self) -> InputResult<(i32, i32)> {
+        Err("The uinput backend can't report the pointer position".into())
+    }
+
+    fn screen_size(&self) -> InputResult<(i32, i32)> {
+        Err("The uinput backend does not know the screen".into())
+    }
+}
+
+/// Translate a button action into batches of evdev events.
+/// Scrolling is emitted as wheel movement, for which press and release have no meaning.
+pub(super) fn button_events(button: Button, direction: Direction) -> Vec<Vec<InputEvent>> {
+    let code = match button {
+        Button::Left => KeyCode::BTN_LEFT,
+        Button::Right => KeyCode::BTN_RIGHT,
+        Button::Middle => KeyCode::BTN_MIDDLE,
+        Button::Back => KeyCode::BTN_SIDE,
+        Button::Forward => KeyCode::BTN_EXTRA,
+        Button::ScrollUp => return scroll_events(RelativeAxisCode::REL_WHEEL, 1, direction),
+        Button::ScrollDown => return scroll_events(RelativeAxisCode::REL_WHEEL, -1, direction),
+        Button::ScrollLeft => return scroll_events(RelativeAxisCode::REL_HWHEEL, -1, direction),
+        Button::ScrollRight => return scroll_events(RelativeAxisCode::REL_HWHEEL, 1, direction),
+    };
+    key_events(code, direction)
+}
+
+/// Find the mouse button of an evdev button code, the inverse of button_events.
+pub fn button_from_key(code: KeyCode) -> Option<Button> {
+    match code {
+        KeyCode::BTN_LEFT => Some(Button::Left),
+        KeyCode::BTN_RIGHT => Some(Button::Right),
+        KeyCode::BTN_MIDDLE => Some(Button::Middle),
+        KeyCode::BTN_SIDE => Some(Button::Back),
+        KeyCode::BTN_EXTRA => Some(Button::Forward),
+        _ => None,
+    }
+}
+
+fn key_events(code: KeyCode, direction: Direction) -> Vec<Vec<InputEvent>> {
+    let press = vec![*KeyEvent::new(code, 1)];
+    let release = vec![*KeyEvent::new(code, 0)];
+    match direction {
+        Direction::Press => vec![press],
+        Direction::Release => vec![release],
+        Direction::Click => vec![press, release],
+    }
+}
+
+fn scroll_events(axis: RelativeAxisCode, value: i32, direction: Direction) -> Vec<Vec<InputEvent>> {
+    match direction {
+        Direction::Release => Vec::new(),
+        Direction::Press | Direction::Click => vec![vec![*RelativeAxisEvent::new(axis, value)]],
+    }
+}
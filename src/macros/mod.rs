@@ -0,0 +1,123 @@
+use crate::input::{InputBackend, InputResult};
+use crate::state::get_state_dir;
+use enigo::{Button, Direction};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+mod recorder;
+
+#[cfg(test)]
+mod test;
+
+pub use recorder::Recorder;
+
+/// The name of the directory next to the state file that holds the macros.
+const MACRO_DIR: &str = "macros";
+
+/// A single input action of a macro.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum MacroAction {
+    /// Move the pointer relative to its position.
+    Move { dx: i32, dy: i32 },
+    Button {
+        button: Button,
+        direction: Direction,
+    },
+    /// Press or release a key, identified by its evdev key code.
+    Key { code: u16, direction: Direction },
+}
+
+impl MacroAction {
+    /// Send the action to the input backend.
+    pub fn play(&self, input: &mut dyn InputBackend) -> InputResult<()> {
+        match *self {
+            MacroAction::Move { dx, dy } => input.move_by(dx, dy),
+            MacroAction::Button { button, direction } => input.button(button, direction),
+            MacroAction::Key { code, direction } => input.key(code, direction),
+        }
+    }
+}
+
+/// An action and the time to wait for it since the previous one.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MacroEvent {
+    pub delay_ms: u64,
+    #[serde(flatten)]
+    pub action: MacroAction,
+}
+
+/// A recorded sequence of input actions.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Macro {
+    pub events: Vec<MacroEvent>,
+}
+
+impl Macro {
+    /// The time it takes to play the macro once at normal speed.
+    pub fn duration(&self) -> Duration {
+        Duration::from_millis(self.events.iter().map(|event| event.delay_ms).sum())
+    }
+
+    /// Load the macro with the given name from the macro directory.
+    pub fn load(name: &str) -> Result<Self, Box<dyn Error>> {
+        let file = fs::File::open(macro_path(name)?)?;
+        Ok(serde_json::from_reader(file)?)
+    }
+
+    /// Save the macro under the given name in the macro directory, replacing an existing one.
+    pub fn save(&self, name: &str) -> Result<(), Box<dyn Error>> {
+        let path = macro_path(name)?;
+        fs::create_dir_all(macro_dir())?;
+        let file = fs::File::create(path)?;
+        serde_json::to_writer(file, self)?;
+        Ok(())
+    }
+}
+
+/// Return the names of all saved macros, sorted alphabetically.
+pub fn list_macros() -> Result<Vec<String>, Box<dyn Error>> {
+    let dir = macro_dir();
+    if !fs::exists(&dir)? {
+        return Ok(Vec::new());
+    }
+    let mut names: Vec<String> = fs::read_dir(dir)?
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if path.extension()? != "json" {
+                return None;
+            }
+            Some(path.file_stem()?.to_str()?.to_string())
+        })
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+/// Delete the saved macro with the given name.
+pub fn delete_macro(name: &str) -> Result<(), Box<dyn Error>> {
+    Ok(fs::remove_file(macro_path(name)?)?)
+}
+
+/// Check if the name can be used to save a macro.
+pub fn validate_name(name: &str) -> Result<(), Box<dyn Error>> {
+    macro_path(name).map(|_| ())
+}
+
+/// The directory next to the state file that holds the macros.
+fn macro_dir() -> PathBuf {
+    PathBuf::from(get_state_dir()).join(MACRO_DIR)
+}
+
+/// The file of the macro with the given name.
+/// Returns an error if the name could point outside of the macro directory.
+fn macro_path(name: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let name = name.trim();
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        return Err(format!("Invalid macro name \"{name}\"").into());
+    }
+    Ok(macro_dir().join(format!("{name}.json")))
+}
@@ -0,0 +1,145 @@
+use super::{Macro, MacroAction, MacroEvent};
+use crate::input::{DeviceReader, button_from_key, open_devices};
+use enigo::{Button, Direction};
+use evdev::{EventSummary, RelativeAxisCode};
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Records the input of all mice and keyboards by reading the devices directly.
+/// Requires read access to /dev/input, usually by being in the input group.
+pub struct Recorder {
+    events: Arc<Mutex<Vec<(Instant, MacroAction)>>>,
+    reader: DeviceReader,
+}
+
+impl Recorder {
+    /// Start recording the input of every mouse and keyboard.
+    /// Returns an error if no input device can be read.
+    pub fn start() -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let devices = open_devices(|_| true, "no input device can be read")?;
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&events);
+        // Every device gets its own clone of the frame, so their movements are not mixed up.
+        let mut frame = Frame::default();
+        let reader = DeviceReader::spawn(devices, move |event| {
+            let actions = frame.handle(event.destructure());
+            if !actions.is_empty() {
+                let now = Instant::now();
+                recorded
+                    .lock()
+                    .unwrap()
+                    .extend(actions.into_iter().map(|action| (now, action)));
+            }
+            true
+        });
+        Ok(Self { events, reader })
+    }
+
+    /// Stop recording and return the recorded macro.
+    pub fn stop(self) -> Macro {
+        self.reader.stop();
+        let events = std::mem::take(&mut *self.events.lock().unwrap());
+        into_macro(events)
+    }
+}
+
+/// Collects the events of a device until the end of the frame, so a diagonal move is a single action.
+/// Only relative movement is recorded, the absolute positions of touchpads, tablets and touchscreens are not.
+#[derive(Clone, Default)]
+pub(super) struct Frame {
+    dx: i32,
+    dy: i32,
+}
+
+impl Frame {
+    /// Handle an event of the device and return the actions it completes.
+    pub(super) fn handle(&mut self, event: EventSummary) -> Vec<MacroAction> {
+        match event {
+            EventSummary::RelativeAxis(_, RelativeAxisCode::REL_X, value) => self.dx += value,
+            EventSummary::RelativeAxis(_, RelativeAxisCode::REL_Y, value) => self.dy += value,
+            EventSummary::RelativeAxis(_, RelativeAxisCode::REL_WHEEL, value) => {
+                let button = if value > 0 {
+                    Button::ScrollUp
+                } else {
+                    Button::ScrollDown
+                };
+                return scroll(button, value);
+            }
+            EventSummary::RelativeAxis(_, RelativeAxisCode::REL_HWHEEL, value) => {
+                let button = if value > 0 {
+                    Button::ScrollRight
+                } else {
+                    Button::ScrollLeft
+                };
+                return scroll(button, value);
+            }
+            // Holding a key repeats it, replaying the press and release is enough.
+            EventSummary::Key(_, key, value @ (0 | 1)) => {
+                let direction = if value == 1 {
+                    Direction::Press
+                } else {
+                    Direction::Release
+                };
+                return vec![match button_from_key(key) {
+                    Some(button) => MacroAction::Button { button, direction },
+                    None => MacroAction::Key {
+                        code: key.code(),
+                        direction,
+                    },
+                }];
+            }
+            EventSummary::Synchronization(..) if self.dx != 0 || self.dy != 0 => {
+                let action = MacroAction::Move {
+                    dx: self.dx,
+                    dy: self.dy,
+                };
+                *self = Self::default();
+                return vec![action];
+            }
+            _ => (),
+        }
+        Vec::new()
+    }
+}
+
+fn scroll(button: Button, value: i32) -> Vec<MacroAction> {
+    let click = MacroAction::Button {
+        button,
+        direction: Direction::Click,
+    };
+    vec![click; value.unsigned_abs() as usize]
+}
+
+/// Turn the recorded actions into a macro.
+/// If the recording was stopped by clicking "Stop Recording", that final click is left out.
+pub(super) fn into_macro(mut events: Vec<(Instant, MacroAction)>) -> Macro {
+    // The devices are read in parallel, so the events might be slightly out of order.
+    events.sort_by_key(|(time, _)| *time);
+    let left = |direction| MacroAction::Button {
+        button: Button::Left,
+        direction,
+    };
+    // Stopping with the keyboard ends with key events instead, so the clicks of the user are kept.
+    if let [.., (_, press), (_, release)] = events.as_slice()
+        && *press == left(Direction::Press)
+        && *release == left(Direction::Release)
+    {
+        events.truncate(events.len() - 2);
+    }
+
+    let mut previous = events.first().map(|(time, _)| *time);
+    let events = events
+        .into_iter()
+        .map(|(time, action)| {
+            let delay = time - previous.unwrap_or(time);
+            previous = Some(time);
+            MacroEvent {
+                delay_ms: delay.as_millis() as u64,
+                action,
+            }
+        })
+        .collect();
+    Macro { events }
+}
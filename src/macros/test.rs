@@ -0,0 +1,244 @@
+use super::recorder::{Frame, into_macro};
+use super::*;
+use crate::state::XDG_STATE_HOME;
+use evdev::{EventSummary, EventType, InputEvent, KeyCode, RelativeAxisCode, SynchronizationCode};
+use serial_test::serial;
+use std::env;
+use std::time::Instant;
+
+fn test_macro() -> Macro {
+    Macro {
+        events: vec![
+            MacroEvent {
+                delay_ms: 0,
+                action: MacroAction::Move { dx: 10, dy: -5 },
+            },
+            MacroEvent {
+                delay_ms: 100,
+                action: MacroAction::Button {
+                    button: Button::Right,
+                    direction: Direction::Click,
+                },
+            },
+            MacroEvent {
+                delay_ms: 50,
+                action: MacroAction::Key {
+                    code: KeyCode::KEY_A.code(),
+                    direction: Direction::Press,
+                },
+            },
+        ],
+    }
+}
+
+fn summary(event_type: EventType, code: u16, value: i32) -> EventSummary {
+    InputEvent::new(event_type.0, code, value).destructure()
+}
+
+#[test]
+fn macro_json_round_trip() {
+    let recorded = test_macro();
+    let json = serde_json::to_string(&recorded).expect("Should serialize macro");
+    assert!(
+        json.contains(r#""type":"move""#),
+        "Actions should be tagged with their type: {json}"
+    );
+    let parsed: Macro = serde_json::from_str(&json).expect("Should parse macro");
+    assert_eq!(recorded, parsed);
+}
+
+#[test]
+fn macro_duration_sums_delays() {
+    assert_eq!(Duration::from_millis(150), test_macro().duration());
+    assert_eq!(Duration::ZERO, Macro::default().duration());
+}
+
+#[test]
+#[serial]
+fn save_load_list_and_delete_macros() {
+    let tmp_dir = tempfile::tempdir().expect("Should create temporary directory");
+    unsafe {
+        env::set_var(XDG_STATE_HOME, tmp_dir.path());
+    }
+
+    assert!(
+        list_macros().expect("Should list macros").is_empty(),
+        "Should have no macros before saving one"
+    );
+
+    let recorded = test_macro();
+    recorded.save("second").expect("Should save macro");
+    recorded.save("first").expect("Should save macro");
+    assert_eq!(
+        vec!["first", "second"],
+        list_macros().expect("Should list macros")
+    );
+    assert_eq!(
+        recorded,
+        Macro::load("first").expect("Should load macro"),
+        "Loaded macro should match the saved one"
+    );
+
+    delete_macro("first").expect("Should delete macro");
+    assert_eq!(vec!["second"], list_macros().expect("Should list macros"));
+    assert!(
+        Macro::load("first").is_err(),
+        "Deleted macro should not load"
+    );
+
+    unsafe {
+        env::remove_var(XDG_STATE_HOME);
+    }
+}
+
+#[test]
+fn macro_names_stay_in_macro_dir() {
+    for name in ["", "  ", "../state", "a/b", "a\\b", ".hidden"] {
+        assert!(
+            macro_path(name).is_err(),
+            "\"{name}\" should be rejected as macro name"
+        );
+    }
+    assert!(macro_path("farm gold").is_ok());
+}
+
+#[test]
+fn frame_merges_movement_until_sync() {
+    let mut frame = Frame::default();
+    assert!(
+        frame
+            .handle(summary(EventType::RELATIVE, RelativeAxisCode::REL_X.0, 3))
+            .is_empty()
+    );
+    assert!(
+        frame
+            .handle(summary(EventType::RELATIVE, RelativeAxisCode::REL_Y.0, -2))
+            .is_empty()
+    );
+    let sync = || {
+        summary(
+            EventType::SYNCHRONIZATION,
+            SynchronizationCode::SYN_REPORT.0,
+            0,
+        )
+    };
+    assert_eq!(
+        vec![MacroAction::Move { dx: 3, dy: -2 }],
+        frame.handle(sync())
+    );
+    assert!(
+        frame.handle(sync()).is_empty(),
+        "Frame without movement should not move"
+    );
+}
+
+#[test]
+fn frame_maps_buttons_keys_and_wheel() {
+    let mut frame = Frame::default();
+    assert_eq!(
+        vec![MacroAction::Button {
+            button: Button::Right,
+            direction: Direction::Press,
+        }],
+        frame.handle(summary(EventType::KEY, KeyCode::BTN_RIGHT.code(), 1))
+    );
+    assert_eq!(
+        vec![MacroAction::Key {
+            code: KeyCode::KEY_A.code(),
+            direction: Direction::Release,
+        }],
+        frame.handle(summary(EventType::KEY, KeyCode::KEY_A.code(), 0))
+    );
+    assert!(
+        frame
+            .handle(summary(EventType::KEY, KeyCode::KEY_A.code(), 2))
+            .is_empty(),
+        "Key repeats should be ignored"
+    );
+    let scroll_down = MacroAction::Button {
+        button: Button::ScrollDown,
+        direction: Direction::Click,
+    };
+    assert_eq!(
+        vec![scroll_down; 2],
+        frame.handle(summary(
+            EventType::RELATIVE,
+            RelativeAxisCode::REL_WHEEL.0,
+            -2
+        ))
+    );
+}
+
+#[test]
+fn into_macro_uses_delays_and_drops_stop_click() {
+    let start = Instant::now();
+    let move_by = MacroAction::Move { dx: 1, dy: 1 };
+    let key = MacroAction::Key {
+        code: KeyCode::KEY_A.code(),
+        direction: Direction::Press,
+    };
+    let left = |direction| MacroAction::Button {
+        button: Button::Left,
+        direction,
+    };
+    let recorded = into_macro(vec![
+        (start + Duration::from_millis(30), key),
+        (start, move_by),
+        (start + Duration::from_millis(80), left(Direction::Press)),
+        (start + Duration::from_millis(90), left(Direction::Release)),
+    ]);
+
+    assert_eq!(
+        vec![
+            MacroEvent {
+                delay_ms: 0,
+                action: move_by,
+            },
+            MacroEvent {
+                delay_ms: 30,
+                action: key,
+            },
+        ],
+        recorded.events,
+        "Events should be sorted and the final click dropped"
+    );
+}
+
+#[test]
+fn into_macro_keeps_clicks_of_the_user() {
+    let start = Instant::now();
+    let left = |direction| MacroAction::Button {
+        button: Button::Left,
+        direction,
+    };
+    let recorded = into_macro(vec![
+        (start, left(Direction::Press)),
+        (start + Duration::from_millis(10), left(Direction::Release)),
+        (start + Duration::from_millis(80), left(Direction::Press)),
+        (start + Duration::from_millis(90), left(Direction::Release)),
+    ]);
+    assert_eq!(
+        vec![left(Direction::Press), left(Direction::Release)],
+        recorded
+            .events
+            .iter()
+            .map(|event| event.action)
+            .collect::<Vec<_>>(),
+        "Only the final click should be dropped"
+    );
+
+    let key = MacroAction::Key {
+        code: KeyCode::KEY_ENTER.code(),
+        direction: Direction::Release,
+    };
+    let recorded = into_macro(vec![
+        (start, left(Direction::Press)),
+        (start + Duration::from_millis(10), left(Direction::Release)),
+        (start + Duration::from_millis(20), key),
+    ]);
+    assert_eq!(
+        3,
+        recorded.events.len(),
+        "Without a final click nothing should be dropped"
+    );
+}
@@ -2,12 +2,30 @@
 // Prevent console window in addition to Slint window in Windows release builds when, e.g., starting the app via file manager. Ignored on other platforms.
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use clap::Parser;
+use cli::{Cli, Overrides};
+use hotkey::{HotkeyAction, HotkeyEvent, HotkeySettings, HotkeyStatus, Hotkeys};
+use input::DeviceReader;
+use scheduler::Scheduler;
+use slint::{Model, ModelRc, SharedString, VecModel};
 use state::State;
 use std::error::Error;
-use std::sync::{Arc, atomic::AtomicU64, atomic::Ordering};
+use std::path::Path;
+use std::sync::{Arc, Mutex, atomic::AtomicU64, atomic::Ordering};
+use std::time::Duration;
+use tokio::signal::unix::{SignalKind, signal};
+use tokio::sync::mpsc::{UnboundedSender, unbounded_channel};
 
 mod autoclicker;
+mod cli;
+mod failsafe;
 mod hotkey;
+mod idle;
+mod input;
+mod macros;
+mod profile;
+mod scheduler;
+mod script;
 mod state;
 
 #[cfg(test)]
@@ -17,18 +35,23 @@ const VERSION: &str = env!("CARGO_PKG_VERSION");
 const COMMIT: Option<&str> = option_env!("CI_COMMIT_SHA");
 const APP_ID: &str = concat!("io.github.heathcliff26.", env!("CARGO_PKG_NAME"));
 
+// Keep in sync with the limits of the delay in the UI.
+const MIN_DELAY: i32 = 20;
+const MAX_DELAY: i32 = 1000;
+const DELAY_STEP: i32 = 10;
+
+/// How long quitting waits for the current run to stop and the hotkeys to be closed.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+
 slint::include_modules!();
 
 // Need 2 threads here, one will be blocked by the Slint event loop.
 #[tokio::main(flavor = "multi_thread", worker_threads = 2)]
 async fn main() -> Result<(), Box<dyn Error>> {
-    let autoclicker = match autoclicker::Autoclicker::new() {
-        Ok(ac) => ac,
-        Err(e) => {
-            eprintln!("Failed to initialize autoclicker: {e}");
-            std::process::exit(1);
-        }
-    };
+    let cli = Cli::parse();
+    if let Some(path) = &cli.run_script {
+        return run_script_headless(path, &cli.overrides).await;
+    }
 
     let app = AppWindow::new()?;
     app.global::<GlobalState>().set_version(VERSION.into());
@@ -41,39 +64,191 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     slint::set_xdg_app_id(APP_ID).expect("Failed to set XDG app ID");
 
-    init_global_state(&app);
+    init_global_state(&app, &cli.overrides);
+
+    // Input is initialized in the background, so the UI can explain the problem if it fails.
+    let autoclicker = autoclicker::Autoclicker::new();
+    init_system_limits(&app, &autoclicker, autoclicker::SYSTEM_LIMITS_FILE);
+    register_retry_input(&app, autoclicker.clone());
+    app.global::<GlobalState>().invoke_retry_input();
+    register_safety_changed(&app, autoclicker.clone());
+    listen_failsafe_escape(&app, &autoclicker);
 
     let autoclicker_delay: u64 = app.global::<GlobalState>().get_delay().try_into().unwrap();
     let autoclicker_delay = Arc::new(AtomicU64::new(autoclicker_delay));
+    let hotkey_settings = Arc::new(Mutex::new(State::from_app(&app).hotkey_settings()));
 
-    let global_hotkey = hotkey::HotkeyPortal::register().await?;
-    autoclicker.trigger_on_hotkey(global_hotkey.clone(), Arc::clone(&autoclicker_delay));
+    let (hotkey_events, hotkey_receiver) = unbounded_channel();
+    let app_weak = app.as_weak();
+    autoclicker.trigger_on_hotkey(
+        hotkey_receiver,
+        Arc::clone(&autoclicker_delay),
+        Arc::clone(&hotkey_settings),
+        move |action| {
+            let res = app_weak.upgrade_in_event_loop(move |app| run_hotkey_action(&app, action));
+            if let Err(e) = res {
+                eprintln!("Failed to run hotkey action {action:?}: {e}");
+            }
+        },
+    );
+    register_retry_mouse_trigger(&app, autoclicker.clone(), hotkey_events.clone());
+    app.global::<GlobalState>().invoke_retry_mouse_trigger();
+    // Not every desktop supports global hotkeys, the app works without them.
+    let hotkeys = Arc::new(Mutex::new(None));
+    register_retry_hotkeys(&app, hotkey_events, Arc::clone(&hotkeys));
+    app.global::<GlobalState>().invoke_retry_hotkeys();
 
-    register_start_auto_click(&app, autoclicker, autoclicker_delay.clone());
-    register_settings_changed(&app, autoclicker_delay);
-    register_configure_hotkey(&app, global_hotkey);
+    register_profiles(&app);
+    register_clicker_status(&app, &autoclicker);
+    register_stop_auto_click(&app, autoclicker.clone());
+    register_toggle_pause(&app, autoclicker.clone());
+    register_macros(&app, autoclicker.clone());
+    register_script_editor(&app, autoclicker.clone());
+    register_scheduler(&app, autoclicker.clone(), autoclicker_delay.clone());
+    register_start_keepalive(&app, autoclicker.clone());
+    register_start_auto_click(&app, autoclicker.clone(), autoclicker_delay.clone());
+    register_settings_changed(
+        &app,
+        autoclicker_delay.clone(),
+        hotkey_settings.clone(),
+        cli.overrides.clone(),
+    );
+    register_state_watcher(
+        &app,
+        autoclicker_delay,
+        hotkey_settings,
+        cli.overrides.clone(),
+    );
 
+    quit_on_signal();
     if let Err(e) = run_app_minimized_to_tray(app.as_weak()) {
         eprintln!("Failed to run app minimized to tray: {e}");
         app.run()?;
     }
 
-    save_global_state(&app);
+    shutdown(&app, &autoclicker, &hotkeys, &cli.overrides).await;
 
     Ok(())
 }
 
+/// Stop clicking, release any held input, save the settings and unregister the hotkeys.
+async fn shutdown(
+    app: &AppWindow,
+    autoclicker: &autoclicker::Autoclicker,
+    hotkeys: &Mutex<Option<Hotkeys>>,
+    overrides: &Overrides,
+) {
+    autoclicker.shutdown(SHUTDOWN_TIMEOUT).await;
+    save_global_state(app, overrides);
+    let registered = hotkeys.lock().unwrap().take();
+    if let Some(hotkeys) = registered
+        && tokio::time::timeout(SHUTDOWN_TIMEOUT, hotkeys.close())
+            .await
+            .is_err()
+    {
+        eprintln!("Closing the hotkeys timed out");
+    }
+}
+
+/// Wait until the process is asked to quit with SIGINT or SIGTERM and return the name of the signal.
+async fn quit_signal() -> std::io::Result<&'static str> {
+    let mut interrupt = signal(SignalKind::interrupt())?;
+    let mut terminate = signal(SignalKind::terminate())?;
+    tokio::select! {
+        _ = interrupt.recv() => Ok("SIGINT"),
+        _ = terminate.recv() => Ok("SIGTERM"),
+    }
+}
+
+/// Quit the event loop like the tray menu does once a quit signal is received,
+/// so the app shuts down cleanly instead of being killed in the middle of a click.
+fn quit_on_signal() {
+    tokio::spawn(async {
+        match quit_signal().await {
+            Ok(name) => println!("Received {name}, quitting"),
+            Err(e) => {
+                eprintln!("Failed to listen for quit signals: {e}");
+                return;
+            }
+        }
+        let res = slint::invoke_from_event_loop(|| {
+            if let Err(e) = slint::quit_event_loop() {
+                eprintln!("Failed to quit the event loop: {e}");
+            }
+        });
+        if let Err(e) = res {
+            eprintln!("Failed to quit the app: {e}");
+        }
+    });
+}
+
+/// Run the script with the saved settings and the overrides and wait until it is done.
+/// Only the input backend, failsafe corner, safety limits, start delay and duration settings apply.
+async fn run_script_headless(path: &Path, overrides: &Overrides) -> Result<(), Box<dyn Error>> {
+    let script = script::Program::load(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let mut state = State::from_file()?.unwrap_or_default();
+    overrides.apply(&mut state);
+
+    let mut autoclicker = autoclicker::Autoclicker::new();
+    apply_system_limits(&autoclicker, autoclicker::SYSTEM_LIMITS_FILE)?;
+    autoclicker
+        .init_input(state.input_backend)
+        .await
+        .map_err(|e| e.to_string())?;
+    autoclicker.failsafe().set_corner(state.failsafe_corner);
+    autoclicker.set_limits(state.limits());
+    if let Err(e) = autoclicker.failsafe().listen_escape() {
+        eprintln!("Pressing Escape will not stop the script: {e}");
+    }
+    if !autoclicker
+        .run_script(
+            Arc::new(script),
+            state.use_start_delay.then_some(state.start_delay),
+            state.use_duration.then_some(state.duration),
+        )
+        .await
+    {
+        return Err("failed to start the script".into());
+    }
+    tokio::select! {
+        _ = autoclicker.wait_until_stopped() => (),
+        Ok(name) = quit_signal() => {
+            println!("Received {name}, stopping the script");
+            autoclicker.shutdown(SHUTDOWN_TIMEOUT).await;
+            return Ok(());
+        }
+    }
+    match autoclicker.watch_status().borrow().clone() {
+        autoclicker::Status::Failed(e) => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
 /// Attempt to initialize the tray icon and run the app. Returns an error if the tray icon could not be initialized.
 fn run_app_minimized_to_tray(app: slint::Weak<AppWindow>) -> Result<(), slint::PlatformError> {
     let tray = TrayIcon::new()?;
+
+    if let Some(app) = app.upgrade() {
+        let global_state = app.global::<GlobalState>();
+        tray.set_next_run(global_state.get_next_job());
+        let tray_weak = tray.as_weak();
+        global_state.on_next_job_changed(move |next_run| {
+            if let Some(tray) = tray_weak.upgrade() {
+                tray.set_next_run(next_run);
+            }
+        });
+    }
+
+    let app_pause = app.clone();
+    tray.on_toggle_pause(move || {
+        if let Some(app) = app_pause.upgrade() {
+            app.global::<GlobalState>().invoke_toggle_pause();
+        }
+    });
+
     tray.on_toggle_window(move || {
-        let Some(app) = app.upgrade() else {
-            return;
-        };
-        if app.window().is_visible() {
-            let _ = app.hide();
-        } else {
-            let _ = app.show();
+        if let Some(app) = app.upgrade() {
+            toggle_window(&app);
         }
     });
 
@@ -84,8 +259,112 @@ fn run_app_minimized_to_tray(app: slint::Weak<AppWindow>) -> Result<(), slint::P
     Ok(())
 }
 
-/// Initialize the global state from the saved state file.
-fn init_global_state(app: &AppWindow) {
+/// Show the window if it is hidden, hide it otherwise.
+fn toggle_window(app: &AppWindow) {
+    if app.window().is_visible() {
+        let _ = app.hide();
+    } else {
+        let _ = app.show();
+    }
+}
+
+/// Run the hotkey actions that change the app instead of the autoclicker.
+fn run_hotkey_action(app: &AppWindow, action: HotkeyAction) {
+    let global_state = app.global::<GlobalState>();
+    let delay = match action {
+        HotkeyAction::RaiseDelay => global_state.get_delay() + DELAY_STEP,
+        HotkeyAction::LowerDelay => global_state.get_delay() - DELAY_STEP,
+        HotkeyAction::NextProfile => return next_profile(app),
+        HotkeyAction::ToggleWindow => return toggle_window(app),
+        _ => return,
+    };
+    global_state.set_delay(delay.clamp(MIN_DELAY, MAX_DELAY));
+    global_state.invoke_settings_changed();
+}
+
+/// Register the callbacks for saving, selecting and deleting profiles.
+fn register_profiles(app: &AppWindow) {
+    let global_state = app.global::<GlobalState>();
+
+    let app_weak = app.as_weak();
+    global_state.on_save_profile(move |name| {
+        let app = app_weak.unwrap();
+        let name = name.trim();
+        if name.is_empty() {
+            return;
+        }
+        let mut state = State::from_app(&app);
+        profile::save(
+            &mut state.profiles,
+            profile::Profile {
+                name: name.to_string(),
+                delay: state.delay,
+                start_delay: state.start_delay,
+                duration: state.duration,
+                use_start_delay: state.use_start_delay,
+                use_duration: state.use_duration,
+            },
+        );
+        state::set_profiles(&app, &state.profiles);
+        let global_state = app.global::<GlobalState>();
+        global_state.set_profile(name.into());
+        global_state.invoke_settings_changed();
+    });
+
+    let app_weak = app.as_weak();
+    global_state.on_select_profile(move |name| {
+        let app = app_weak.unwrap();
+        let state = State::from_app(&app);
+        match state
+            .profiles
+            .iter()
+            .find(|profile| profile.name == name.as_str())
+        {
+            Some(profile) => apply_profile(&app, profile),
+            None => eprintln!("There is no profile \"{name}\""),
+        }
+    });
+
+    let app_weak = app.as_weak();
+    global_state.on_delete_profile(move |name| {
+        let app = app_weak.unwrap();
+        let mut state = State::from_app(&app);
+        state
+            .profiles
+            .retain(|profile| profile.name != name.as_str());
+        state::set_profiles(&app, &state.profiles);
+        let global_state = app.global::<GlobalState>();
+        if global_state.get_profile() == name {
+            global_state.set_profile(SharedString::new());
+        }
+        global_state.invoke_settings_changed();
+    });
+}
+
+/// Load the profile after the active one, see `profile::next`.
+fn next_profile(app: &AppWindow) {
+    let state = State::from_app(app);
+    match profile::next(&state.profiles, &state.profile) {
+        Some(profile) => apply_profile(app, profile),
+        None => println!("There are no profiles to switch to"),
+    }
+}
+
+/// Load the click settings of the profile into the app page and save them.
+fn apply_profile(app: &AppWindow, profile: &profile::Profile) {
+    println!("Switching to profile \"{}\"", profile.name);
+    let global_state = app.global::<GlobalState>();
+    global_state.set_delay(profile.delay as i32);
+    global_state.set_start_delay(profile.start_delay as i32);
+    global_state.set_duration(profile.duration as i32);
+    global_state.set_use_start_delay(profile.use_start_delay);
+    global_state.set_use_duration(profile.use_duration);
+    global_state.set_profile(profile.name.as_str().into());
+    global_state.invoke_settings_changed();
+}
+
+/// Initialize the global state from the saved state file and apply the overrides on top.
+fn init_global_state(app: &AppWindow, overrides: &Overrides) {
     let state = match State::from_file() {
         Ok(state) => state,
         Err(e) => {
@@ -93,19 +372,147 @@ fn init_global_state(app: &AppWindow) {
             None
         }
     };
-    if let Some(state) = state {
-        state.update_app(app);
+    if state.is_none() && overrides.is_empty() {
+        return;
     }
+    let mut state = state.unwrap_or_else(|| State::from_app(app));
+    overrides.apply(&mut state);
+    state.update_app(app);
 }
 
 /// Save the global state to file.
-fn save_global_state(app: &AppWindow) {
-    let state = State::from_app(app);
+/// Unless requested otherwise, overridden settings keep their saved value.
+fn save_global_state(app: &AppWindow, overrides: &Overrides) {
+    let mut state = State::from_app(app);
+    if !overrides.save_overrides && !overrides.is_empty() {
+        match State::from_file() {
+            Ok(Some(saved)) => overrides.revert(&mut state, &saved),
+            // Nothing has been saved yet, the overridden settings keep their defaults.
+            Ok(None) => overrides.revert(&mut state, &State::default()),
+            Err(e) => {
+                eprintln!("Failed to load saved state, not saving overrides: {e}");
+                return;
+            }
+        }
+    }
     if let Err(e) = state.save_to_file() {
         eprintln!("Failed to save state: {e}");
     }
 }
 
+/// Apply a state that was changed outside of the app.
+/// Changes are applied last-writer-wins: settings changed in the UI are saved immediately,
+/// so the file always holds the most recent change from either side.
+fn apply_external_state(
+    app: &AppWindow,
+    mut state: State,
+    autoclicker_delay: &AtomicU64,
+    hotkey_settings: &Mutex<HotkeySettings>,
+    overrides: &Overrides,
+) {
+    overrides.apply(&mut state);
+    let current = State::from_app(app);
+    if current == state {
+        return;
+    }
+    println!("State file changed, reloading settings");
+    state.update_app(app);
+    autoclicker_delay.store(state.delay, Ordering::Release);
+    *hotkey_settings.lock().unwrap() = state.hotkey_settings();
+    if current.input_backend != state.input_backend {
+        app.global::<GlobalState>().invoke_retry_input();
+    }
+    if current.hotkey_backend != state.hotkey_backend
+        || current.hotkey_trigger != state.hotkey_trigger
+    {
+        app.global::<GlobalState>().invoke_retry_hotkeys();
+    }
+    if current.mouse_trigger != state.mouse_trigger {
+        app.global::<GlobalState>().invoke_retry_mouse_trigger();
+    }
+    if current.jobs != state.jobs {
+        app.global::<GlobalState>().invoke_jobs_changed();
+    }
+}
+
+/// Watch the state file and apply external changes to the app.
+fn register_state_watcher(
+    app: &AppWindow,
+    autoclicker_delay: Arc<AtomicU64>,
+    hotkey_settings: Arc<Mutex<HotkeySettings>>,
+    overrides: Overrides,
+) {
+    let app_weak = app.as_weak();
+
+    let res = state::watch_state_file(move |state| {
+        let autoclicker_delay = Arc::clone(&autoclicker_delay);
+        let hotkey_settings = Arc::clone(&hotkey_settings);
+        let overrides = overrides.clone();
+        let res = app_weak.upgrade_in_event_loop(move |app| {
+            apply_external_state(
+                &app,
+                state,
+                &autoclicker_delay,
+                &hotkey_settings,
+                &overrides,
+            );
+        });
+        if let Err(e) = res {
+            eprintln!("Failed to apply state file changes: {e}");
+        }
+    });
+    if let Err(e) = res {
+        eprintln!("Failed to watch state file: {e}");
+    }
+}
+
+/// Show whether virtual input is available, an empty error means it is.
+fn set_input_status(app: &AppWindow, error: &str) {
+    let global_state = app.global::<GlobalState>();
+    global_state.set_input_ready(error.is_empty());
+    global_state.set_input_error(error.into());
+}
+
+/// Register the callback for (re-)initializing virtual input with the selected backend.
+fn register_retry_input(app: &AppWindow, autoclicker: autoclicker::Autoclicker) {
+    let app_weak = app.as_weak();
+
+    app.global::<GlobalState>().on_retry_input({
+        move || {
+            let app = app_weak.unwrap();
+            let input_backend = State::from_app(&app).input_backend;
+            // The current backend is replaced, so clicking is disabled until it is done.
+            app.global::<GlobalState>().set_input_ready(false);
+
+            let autoclicker = autoclicker.clone();
+            let app_weak = app_weak.clone();
+            tokio::spawn(async move {
+                let error = match autoclicker.init_input(input_backend).await {
+                    Ok(()) => String::new(),
+                    Err(e) => {
+                        eprintln!("Failed to initialize virtual input: {e}");
+                        e.to_string()
+                    }
+                };
+                // Enigo might have fallen back to uinput, which can't report the pointer position.
+                let pointer_known = !error.is_empty() || autoclicker.can_locate_pointer().await;
+                if !pointer_known {
+                    eprintln!(
+                        "The pointer position is not known, the failsafe corner does not work"
+                    );
+                }
+                let res = app_weak.upgrade_in_event_loop(move |app| {
+                    set_input_status(&app, &error);
+                    app.global::<GlobalState>().set_pointer_known(pointer_known);
+                });
+                if let Err(e) = res {
+                    eprintln!("Failed to show virtual input status: {e}");
+                }
+            });
+        }
+    });
+}
+
 /// Register the callback for clicking the "Start Auto-click" button.
 fn register_start_auto_click(
     app: &AppWindow,
@@ -117,16 +524,7 @@ fn register_start_auto_click(
     app.global::<GlobalState>().on_start_auto_click({
         move || {
             let app = app_weak.unwrap();
-            let global_state = app.global::<GlobalState>();
-
-            let start_delay: Option<u64> = match global_state.get_use_start_delay() {
-                true => Some(global_state.get_start_delay().try_into().unwrap()),
-                false => None,
-            };
-            let duration: Option<u64> = match global_state.get_use_duration() {
-                true => Some(global_state.get_duration().try_into().unwrap()),
-                false => None,
-            };
+            let (start_delay, duration) = run_timing(&app);
 
             let delay = Arc::clone(&autoclicker_delay);
 
@@ -138,8 +536,440 @@ fn register_start_auto_click(
     });
 }
 
+/// Register the callback for clicking the "Keep Alive" button.
+fn register_start_keepalive(app: &AppWindow, autoclicker: autoclicker::Autoclicker) {
+    let app_weak = app.as_weak();
+
+    app.global::<GlobalState>().on_start_keepalive(move || {
+        let app = app_weak.unwrap();
+        let state = State::from_app(&app);
+        let idle = Duration::from_secs(state.keepalive_minutes * 60);
+
+        let mut autoclicker = autoclicker.clone();
+        let app_weak = app_weak.clone();
+        tokio::spawn(async move {
+            match idle::IdleMonitor::new(state.idle_backend).await {
+                Ok(monitor) => {
+                    autoclicker
+                        .keep_alive(monitor, idle, state.keepalive_action)
+                        .await;
+                }
+                Err(e) => {
+                    eprintln!("Failed to detect inactivity: {e}");
+                    let status =
+                        autoclicker::Status::Failed(format!("can't detect inactivity: {e}"));
+                    let res = app_weak.upgrade_in_event_loop(move |app| {
+                        set_clicker_status(&app, status);
+                    });
+                    if let Err(e) = res {
+                        eprintln!("Failed to show keep-alive error: {e}");
+                    }
+                }
+            }
+        });
+    });
+}
+
+/// The start delay and duration of runs started from the UI, None if they are disabled.
+fn run_timing(app: &AppWindow) -> (Option<u64>, Option<u64>) {
+    let global_state = app.global::<GlobalState>();
+
+    let start_delay: Option<u64> = match global_state.get_use_start_delay() {
+        true => Some(global_state.get_start_delay().try_into().unwrap()),
+        false => None,
+    };
+    let duration: Option<u64> = match global_state.get_use_duration() {
+        true => Some(global_state.get_duration().try_into().unwrap()),
+        false => None,
+    };
+    (start_delay, duration)
+}
+
+/// Register the callbacks of the macros page and show the saved macros.
+fn register_macros(app: &AppWindow, autoclicker: autoclicker::Autoclicker) {
+    show_macros(app);
+    let global_state = app.global::<GlobalState>();
+    // The name the macro will be saved under and the running recording.
+    let recording: Arc<Mutex<Option<(String, macros::Recorder)>>> = Arc::default();
+
+    let app_weak = app.as_weak();
+    let recorder = Arc::clone(&recording);
+    global_state.on_record_macro(move |name| {
+        let app = app_weak.unwrap();
+        let global_state = app.global::<GlobalState>();
+        let res = macros::validate_name(&name)
+            .and_then(|_| macros::Recorder::start().map_err(|e| e.to_string().into()));
+        match res {
+            Ok(recorder_started) => {
+                println!("Recording macro \"{name}\"");
+                *recorder.lock().unwrap() = Some((name.to_string(), recorder_started));
+                global_state.set_macro_recording(true);
+                global_state.set_macro_error("".into());
+            }
+            Err(e) => {
+                eprintln!("Failed to record macro: {e}");
+                global_state.set_macro_error(format!("Failed to record macro: {e}").into());
+            }
+        }
+    });
+
+    let app_weak = app.as_weak();
+    global_state.on_stop_recording(move || {
+        let app = app_weak.unwrap();
+        let global_state = app.global::<GlobalState>();
+        global_state.set_macro_recording(false);
+        let Some((name, recorder)) = recording.lock().unwrap().take() else {
+            return;
+        };
+        let recorded = recorder.stop();
+        let res = if recorded.events.is_empty() {
+            Err("nothing was recorded".into())
+        } else {
+            recorded.save(&name)
+        };
+        match res {
+            Ok(()) => {
+                println!(
+                    "Saved macro \"{name}\" with {} actions",
+                    recorded.events.len()
+                );
+                global_state.set_macro_error("".into());
+            }
+            Err(e) => {
+                eprintln!("Failed to save macro \"{name}\": {e}");
+                global_state.set_macro_error(format!("Failed to save macro: {e}").into());
+            }
+        }
+        show_macros(&app);
+    });
+
+    let app_weak = app.as_weak();
+    global_state.on_play_macro(move |name| {
+        let app = app_weak.unwrap();
+        let global_state = app.global::<GlobalState>();
+        let recorded = match macros::Macro::load(&name) {
+            Ok(recorded) => Arc::new(recorded),
+            Err(e) => {
+                eprintln!("Failed to load macro \"{name}\": {e}");
+                global_state.set_macro_error(format!("Failed to load macro: {e}").into());
+                return;
+            }
+        };
+        global_state.set_macro_error("".into());
+        println!(
+            "Playing macro \"{name}\", one loop takes {:.1} s",
+            recorded.duration().as_secs_f64()
+        );
+        let state = State::from_app(&app);
+        let (start_delay, duration) = run_timing(&app);
+
+        let mut autoclicker = autoclicker.clone();
+        tokio::spawn(async move {
+            autoclicker
+                .play_macro(
+                    recorded,
+                    state.macro_speed,
+                    state.macro_loops,
+                    start_delay,
+                    duration,
+                )
+                .await;
+        });
+    });
+
+    let app_weak = app.as_weak();
+    global_state.on_delete_macro(move |name| {
+        let app = app_weak.unwrap();
+        if let Err(e) = macros::delete_macro(&name) {
+            eprintln!("Failed to delete macro \"{name}\": {e}");
+            app.global::<GlobalState>()
+                .set_macro_error(format!("Failed to delete macro: {e}").into());
+        }
+        show_macros(&app);
+    });
+}
+
+/// Register the callbacks of the script editor.
+fn register_script_editor(app: &AppWindow, autoclicker: autoclicker::Autoclicker) {
+    let global_state = app.global::<GlobalState>();
+
+    let app_weak = app.as_weak();
+    global_state.on_open_script(move || {
+        let app = app_weak.unwrap();
+        let global_state = app.global::<GlobalState>();
+        let path = global_state.get_script_path();
+        match std::fs::read_to_string(path.as_str()) {
+            Ok(text) => {
+                global_state.set_script_text(text.into());
+                let language = match script::Language::from_path(path.as_str()) {
+                    script::Language::Actions => "actions",
+                    script::Language::Rhai => "rhai",
+                };
+                global_state.set_script_language(language.into());
+                set_script_message(&app, Ok(format!("Opened {path}")));
+            }
+            Err(e) => set_script_message(&app, Err(format!("Failed to open {path}: {e}"))),
+        }
+    });
+
+    let app_weak = app.as_weak();
+    global_state.on_save_script(move || {
+        let app = app_weak.unwrap();
+        let global_state = app.global::<GlobalState>();
+        let path = global_state.get_script_path();
+        let res = std::fs::write(path.as_str(), global_state.get_script_text().as_str());
+        match res {
+            Ok(()) => set_script_message(&app, Ok(format!("Saved {path}"))),
+            Err(e) => set_script_message(&app, Err(format!("Failed to save {path}: {e}"))),
+        }
+    });
+
+    let app_weak = app.as_weak();
+    global_state.on_check_script(move || {
+        let app = app_weak.unwrap();
+        let res = editor_program(&app).map(|_| "The script is valid".to_string());
+        set_script_message(&app, res);
+    });
+
+    let app_weak = app.as_weak();
+    global_state.on_run_script(move || {
+        let app = app_weak.unwrap();
+        let parsed = match editor_program(&app) {
+            Ok(parsed) => Arc::new(parsed),
+            Err(e) => {
+                set_script_message(&app, Err(e));
+                return;
+            }
+        };
+        set_script_message(&app, Ok(String::new()));
+        let (start_delay, duration) = run_timing(&app);
+
+        let mut autoclicker = autoclicker.clone();
+        tokio::spawn(async move {
+            autoclicker.run_script(parsed, start_delay, duration).await;
+        });
+    });
+}
+
+/// Parse the script in the editor in the selected language.
+fn editor_program(app: &AppWindow) -> Result<script::Program, String> {
+    let global_state = app.global::<GlobalState>();
+    let language = script::Language::from_name(&global_state.get_script_language());
+    script::Program::parse(language, &global_state.get_script_text())
+}
+
+/// Show the result of an action of the script editor.
+fn set_script_message(app: &AppWindow, message: Result<String, String>) {
+    let global_state = app.global::<GlobalState>();
+    global_state.set_script_failed(message.is_err());
+    let message = message.unwrap_or_else(|e| e);
+    global_state.set_script_message(message.into());
+}
+
+/// Show the names of the saved macros on the macros page.
+fn show_macros(app: &AppWindow) {
+    let names = match macros::list_macros() {
+        Ok(names) => names,
+        Err(e) => {
+            eprintln!("Failed to list macros: {e}");
+            Vec::new()
+        }
+    };
+    let names: Vec<SharedString> = names.into_iter().map(SharedString::from).collect();
+    app.global::<GlobalState>()
+        .set_macros(ModelRc::new(VecModel::from(names)));
+}
+
+/// Register the callbacks of the schedule page and start the runs of the jobs when they are due.
+/// Jobs use the current delay and the start delay and duration of the settings, unless they set their own duration.
+fn register_scheduler(
+    app: &AppWindow,
+    autoclicker: autoclicker::Autoclicker,
+    autoclicker_delay: Arc<AtomicU64>,
+) {
+    let scheduler = Arc::new(Mutex::new(Scheduler::new(scheduler::LocalClock)));
+    let global_state = app.global::<GlobalState>();
+
+    let app_weak = app.as_weak();
+    let jobs_scheduler = Arc::clone(&scheduler);
+    global_state.on_jobs_changed(move || {
+        let app = app_weak.unwrap();
+        jobs_scheduler
+            .lock()
+            .unwrap()
+            .set_jobs(State::from_app(&app).jobs);
+        show_jobs(&app, &jobs_scheduler.lock().unwrap());
+    });
+
+    let app_weak = app.as_weak();
+    global_state.on_add_job(move |schedule, duration, profile| {
+        let app = app_weak.unwrap();
+        let global_state = app.global::<GlobalState>();
+        let schedule = schedule.trim();
+        if let Err(e) = schedule.parse::<scheduler::Schedule>() {
+            global_state.set_job_error(e.into());
+            return;
+        }
+        global_state.set_job_error(SharedString::new());
+        let mut jobs: Vec<ScheduledJob> = global_state.get_jobs().iter().collect();
+        jobs.push(ScheduledJob {
+            schedule: schedule.into(),
+            duration,
+            profile,
+            next_run: SharedString::new(),
+        });
+        global_state.set_jobs(ModelRc::new(VecModel::from(jobs)));
+        global_state.invoke_jobs_changed();
+        global_state.invoke_settings_changed();
+    });
+
+    let app_weak = app.as_weak();
+    global_state.on_remove_job(move |index| {
+        let app = app_weak.unwrap();
+        let global_state = app.global::<GlobalState>();
+        let mut jobs: Vec<ScheduledJob> = global_state.get_jobs().iter().collect();
+        if index < 0 || index as usize >= jobs.len() {
+            return;
+        }
+        jobs.remove(index as usize);
+        global_state.set_jobs(ModelRc::new(VecModel::from(jobs)));
+        global_state.invoke_jobs_changed();
+        global_state.invoke_settings_changed();
+    });
+
+    global_state.invoke_jobs_changed();
+
+    let app_due = app.as_weak();
+    let app_changed = app.as_weak();
+    let shown_scheduler = Arc::clone(&scheduler);
+    scheduler::run(
+        scheduler,
+        scheduler::POLL_INTERVAL,
+        move |job| {
+            let autoclicker = autoclicker.clone();
+            let delay = Arc::clone(&autoclicker_delay);
+            let res = app_due.upgrade_in_event_loop(move |app| {
+                start_scheduled_job(&app, autoclicker, delay, job);
+            });
+            if let Err(e) = res {
+                eprintln!("Failed to start scheduled job: {e}");
+            }
+        },
+        move || {
+            let scheduler = Arc::clone(&shown_scheduler);
+            let res = app_changed
+                .upgrade_in_event_loop(move |app| show_jobs(&app, &scheduler.lock().unwrap()));
+            if let Err(e) = res {
+                eprintln!("Failed to show next scheduled runs: {e}");
+            }
+        },
+    );
+}
+
+/// Load the profile of the job, if it has one, and start clicking with the settings.
+fn start_scheduled_job(
+    app: &AppWindow,
+    mut autoclicker: autoclicker::Autoclicker,
+    delay: Arc<AtomicU64>,
+    job: scheduler::Job,
+) {
+    if !job.profile.is_empty() {
+        let state = State::from_app(app);
+        match state
+            .profiles
+            .iter()
+            .find(|profile| profile.name == job.profile)
+        {
+            Some(profile) => apply_profile(app, profile),
+            None => eprintln!(
+                "Scheduled job \"{}\" uses the current settings, there is no profile \"{}\"",
+                job.schedule, job.profile
+            ),
+        }
+    }
+    let (start_delay, duration) = run_timing(app);
+    let duration = job.duration.or(duration);
+    tokio::spawn(async move {
+        if !autoclicker.autoclick(delay, start_delay, duration).await {
+            eprintln!("Could not start scheduled job \"{}\"", job.schedule);
+        }
+    });
+}
+
+/// Show the next run of every job and the earliest of them.
+fn show_jobs(app: &AppWindow, scheduler: &Scheduler) {
+    let global_state = app.global::<GlobalState>();
+    let jobs: Vec<ScheduledJob> = global_state
+        .get_jobs()
+        .iter()
+        .zip(scheduler.next_runs())
+        .map(|(job, next)| ScheduledJob {
+            next_run: scheduler::describe_next_run(next).into(),
+            ..job
+        })
+        .collect();
+    global_state.set_jobs(ModelRc::new(VecModel::from(jobs)));
+    let next_job = match scheduler.next_run() {
+        Some(next) => scheduler::describe_next_run(Some(next)),
+        None => String::new(),
+    };
+    global_state.set_next_job(next_job.into());
+}
+
+/// Register the callback for clicking the "Stop Auto-click" button.
+fn register_stop_auto_click(app: &AppWindow, autoclicker: autoclicker::Autoclicker) {
+    app.global::<GlobalState>()
+        .on_stop_auto_click(move || autoclicker.stop());
+}
+
+/// Register the callback for the pause/resume button and tray menu entry.
+fn register_toggle_pause(app: &AppWindow, autoclicker: autoclicker::Autoclicker) {
+    app.global::<GlobalState>().on_toggle_pause(move || {
+        let autoclicker = autoclicker.clone();
+        tokio::spawn(async move { autoclicker.toggle_pause().await });
+    });
+}
+
+/// Show what the autoclicker is doing.
+fn set_clicker_status(app: &AppWindow, status: autoclicker::Status) {
+    let global_state = app.global::<GlobalState>();
+    global_state.set_clicker_running(matches!(
+        status,
+        autoclicker::Status::Countdown(_)
+            | autoclicker::Status::Clicking
+            | autoclicker::Status::Playing
+            | autoclicker::Status::Scripting
+            | autoclicker::Status::KeepingAlive
+            | autoclicker::Status::Paused
+    ));
+    global_state.set_clicker_paused(status == autoclicker::Status::Paused);
+    global_state.set_clicker_status(status.describe().into());
+}
+
+/// Keep the status of the autoclicker shown on the main page up to date.
+fn register_clicker_status(app: &AppWindow, autoclicker: &autoclicker::Autoclicker) {
+    let app_weak = app.as_weak();
+    let mut status = autoclicker.watch_status();
+
+    tokio::spawn(async move {
+        while status.changed().await.is_ok() {
+            let current = status.borrow_and_update().clone();
+            let res = app_weak.upgrade_in_event_loop(move |app| set_clicker_status(&app, current));
+            if let Err(e) = res {
+                eprintln!("Failed to show autoclicker status: {e}");
+                return;
+            }
+        }
+    });
+}
+
 /// Register the callback for setting changes.
-fn register_settings_changed(app: &AppWindow, autoclicker_delay: Arc<AtomicU64>) {
+fn register_settings_changed(
+    app: &AppWindow,
+    autoclicker_delay: Arc<AtomicU64>,
+    hotkey_settings: Arc<Mutex<HotkeySettings>>,
+    overrides: Overrides,
+) {
     let app_weak = app.as_weak();
 
     app.global::<GlobalState>().on_settings_changed({
@@ -151,19 +981,213 @@ fn register_settings_changed(app: &AppWindow, autoclicker_delay: Arc<AtomicU64>)
                 global_state.get_delay().try_into().unwrap(),
                 Ordering::Release,
             );
+            *hotkey_settings.lock().unwrap() = State::from_app(&app).hotkey_settings();
+
+            save_global_state(&app, &overrides);
+        }
+    });
+}
+
+/// Show whether global hotkeys are available, an empty error means they are.
+fn set_hotkey_status(app: &AppWindow, error: &str) {
+    let global_state = app.global::<GlobalState>();
+    global_state.set_hotkeys_available(error.is_empty());
+    global_state.set_hotkey_error(error.into());
+}
+
+/// Register the callback for (re-)registering the global hotkeys with the selected backend.
+/// The registered hotkeys are kept in `registered`, so they can be closed before registering
+/// them again and when the app quits.
+fn register_retry_hotkeys(
+    app: &AppWindow,
+    events: UnboundedSender<HotkeyEvent>,
+    registered: Arc<Mutex<Option<Hotkeys>>>,
+) {
+    let app_weak = app.as_weak();
+
+    app.global::<GlobalState>().on_retry_hotkeys({
+        move || {
+            let app = app_weak.unwrap();
+            let state = State::from_app(&app);
+            // Hides the retry button until the registration is done.
+            app.global::<GlobalState>().set_hotkey_error("".into());
+
+            let events = events.clone();
+            let app_weak = app_weak.clone();
+            let registered = Arc::clone(&registered);
+            tokio::spawn(async move {
+                // Otherwise grabs and readers of a partially working backend stay active.
+                let previous = registered.lock().unwrap().take();
+                if let Some(previous) = previous {
+                    previous.close().await;
+                }
+                let res =
+                    Hotkeys::register(state.hotkey_backend, &state.hotkey_trigger, events).await;
+                let res = app_weak.upgrade_in_event_loop(move |app| match res {
+                    Ok(hotkeys) => {
+                        *registered.lock().unwrap() = Some(hotkeys.clone());
+                        register_hotkey_bindings(&app, &hotkeys);
+                        register_hotkey_connection(&app, &hotkeys);
+                        app.global::<GlobalState>()
+                            .set_hotkeys_configurable(hotkeys.can_configure());
+                        register_configure_hotkey(&app, hotkeys);
+                        set_hotkey_status(&app, "");
+                    }
+                    Err(e) => {
+                        eprintln!("Failed to register global hotkeys: {e}");
+                        set_hotkey_status(&app, &e.to_string());
+                    }
+                });
+                if let Err(e) = res {
+                    eprintln!("Failed to show global hotkey status: {e}");
+                }
+            });
+        }
+    });
+}
+
+/// Load the limits of the administrator into the autoclicker, the settings can only make them stricter.
+/// A file that can't be read is an error, so a mistake in it does not turn the limits off.
+fn apply_system_limits(
+    autoclicker: &autoclicker::Autoclicker,
+    path: &str,
+) -> Result<autoclicker::Limits, Box<dyn Error>> {
+    let limits = autoclicker::Limits::from_system_path(path)
+        .map_err(|e| format!("Failed to load the limits of the administrator from {path}: {e}"))?;
+    if limits != autoclicker::Limits::default() {
+        println!(
+            "The administrator limits every run to {}",
+            limits.describe()
+        );
+    }
+    autoclicker.set_system_limits(limits);
+    Ok(limits)
+}
+
+/// Apply the limits of the administrator and show them.
+/// If they can't be loaded the app still starts, but shows the error and refuses every run.
+fn init_system_limits(app: &AppWindow, autoclicker: &autoclicker::Autoclicker, path: &str) {
+    let global_state = app.global::<GlobalState>();
+    match apply_system_limits(autoclicker, path) {
+        Ok(limits) => global_state.set_system_limits(limits.describe().into()),
+        Err(e) => {
+            eprintln!("{e}, refusing to run the autoclicker");
+            autoclicker.refuse_runs(e.to_string());
+            global_state.set_system_limits_error(e.to_string().into());
+        }
+    }
+}
+
+/// Apply the failsafe settings and safety limits to the autoclicker now and whenever they change.
+fn register_safety_changed(app: &AppWindow, autoclicker: autoclicker::Autoclicker) {
+    let app_weak = app.as_weak();
+
+    app.global::<GlobalState>().on_safety_changed(move || {
+        let state = State::from_app(&app_weak.unwrap());
+        autoclicker.failsafe().set_corner(state.failsafe_corner);
+        autoclicker.set_limits(state.limits());
+    });
+    app.global::<GlobalState>().invoke_safety_changed();
+}
+
+/// Stop every run when Escape is pressed repeatedly.
+/// Escape is read from the input devices, so it works even if the global hotkeys don't.
+fn listen_failsafe_escape(app: &AppWindow, autoclicker: &autoclicker::Autoclicker) {
+    let error = match autoclicker.failsafe().listen_escape() {
+        Ok(()) => String::new(),
+        Err(e) => {
+            eprintln!("Failed to listen for Escape: {e}");
+            e.to_string()
+        }
+    };
+    app.global::<GlobalState>().set_failsafe_error(error.into());
+}
+
+/// Register the callback for listening to the selected mouse button again.
+/// The reader of the previous button is dropped first, so only one button triggers the autoclicker.
+fn register_retry_mouse_trigger(
+    app: &AppWindow,
+    autoclicker: autoclicker::Autoclicker,
+    events: UnboundedSender<HotkeyEvent>,
+) {
+    let app_weak = app.as_weak();
+    let reader: Mutex<Option<DeviceReader>> = Mutex::new(None);
+
+    app.global::<GlobalState>().on_retry_mouse_trigger(move || {
+        let app = app_weak.unwrap();
+        let mut reader = reader.lock().unwrap();
+        *reader = None;
+        *reader = listen_mouse_trigger(&app, &autoclicker, events.clone());
+    });
+}
+
+/// Listen for the selected mouse button, it works like the toggle hotkey.
+/// The autoclicker never clicks the button itself, so it can't trigger itself.
+/// Listening stops once the returned reader is dropped.
+fn listen_mouse_trigger(
+    app: &AppWindow,
+    autoclicker: &autoclicker::Autoclicker,
+    events: UnboundedSender<HotkeyEvent>,
+) -> Option<DeviceReader> {
+    let mouse_trigger = State::from_app(app).mouse_trigger;
+    autoclicker.exclude_button(mouse_trigger.button());
+    let (reader, error) = match mouse_trigger.listen(events) {
+        Ok(reader) => (reader, String::new()),
+        Err(e) => {
+            eprintln!("Failed to listen for the mouse button trigger: {e}");
+            (None, e.to_string())
+        }
+    };
+    app.global::<GlobalState>()
+        .set_mouse_trigger_error(error.into());
+    reader
+}
+
+/// Show whether the running hotkey backend works or is reconnecting.
+fn set_hotkey_connection(app: &AppWindow, status: HotkeyStatus) {
+    let global_state = app.global::<GlobalState>();
+    global_state.set_hotkeys_available(status.is_active());
+    global_state.set_hotkey_connection(status.describe().into());
+}
+
+/// Keep the connection status of the hotkeys shown in the settings up to date.
+fn register_hotkey_connection(app: &AppWindow, hotkeys: &Hotkeys) {
+    let app_weak = app.as_weak();
+
+    hotkeys.watch_status(move |status| {
+        let res = app_weak.upgrade_in_event_loop(move |app| set_hotkey_connection(&app, status));
+        if let Err(e) = res {
+            eprintln!("Failed to show hotkey status: {e}");
+        }
+    });
+}
+
+/// Show the triggers bound to the hotkeys in the settings.
+fn set_hotkey_bindings(app: &AppWindow, bindings: Vec<String>) {
+    let bindings: Vec<SharedString> = bindings.into_iter().map(Into::into).collect();
+    app.global::<GlobalState>()
+        .set_hotkeys(ModelRc::new(VecModel::from(bindings)));
+}
+
+/// Keep the bound hotkeys shown in the settings up to date.
+fn register_hotkey_bindings(app: &AppWindow, hotkeys: &Hotkeys) {
+    let app_weak = app.as_weak();
 
-            save_global_state(&app);
+    hotkeys.watch_bindings(move |bindings| {
+        let res = app_weak.upgrade_in_event_loop(move |app| set_hotkey_bindings(&app, bindings));
+        if let Err(e) = res {
+            eprintln!("Failed to show bound hotkeys: {e}");
         }
     });
 }
 
 /// Register the callback for configuring the hotkey.
-fn register_configure_hotkey(app: &AppWindow, global_hotkey: hotkey::HotkeyPortal) {
+fn register_configure_hotkey(app: &AppWindow, hotkeys: Hotkeys) {
     app.global::<GlobalState>().on_configure_hotkey({
         move || {
-            let global_hotkey = global_hotkey.clone();
+            let hotkeys = hotkeys.clone();
             tokio::spawn(async move {
-                global_hotkey.configure_hotkey().await;
+                hotkeys.configure().await;
             });
         }
     });
@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+mod test;
+
+/// A named set of the click settings of the app page, the next profile hotkey switches between them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    pub delay: u64,
+    pub start_delay: u64,
+    pub duration: u64,
+    pub use_start_delay: bool,
+    pub use_duration: bool,
+}
+
+impl Profile {
+    /// Limit every value to the range allowed in the UI, like the settings of the state file.
+    pub fn clamp(&mut self) {
+        self.delay = self.delay.clamp(20, 1000);
+        self.start_delay = self.start_delay.clamp(1, 60);
+        self.duration = self.duration.clamp(1, 60);
+    }
+}
+
+/// Add the profile, or replace the one with the same name.
+pub fn save(profiles: &mut Vec<Profile>, profile: Profile) {
+    match profiles.iter_mut().find(|saved| saved.name == profile.name) {
+        Some(saved) => *saved = profile,
+        None => profiles.push(profile),
+    }
+}
+
+/// Find the profile after the one with the given name, after the last one the first follows.
+/// Starts with the first profile if there is none with the name.
+pub fn next<'a>(profiles: &'a [Profile], current: &str) -> Option<&'a Profile> {
+    let next = match profiles.iter().position(|profile| profile.name == current) {
+        Some(index) => (index + 1) % profiles.len(),
+        None => 0,
+    };
+    profiles.get(next)
+}
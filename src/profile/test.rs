@@ -0,0 +1,58 @@
+use super::*;
+
+fn profile(name: &str, delay: u64) -> Profile {
+    Profile {
+        name: name.to_string(),
+        delay,
+        start_delay: 1,
+        duration: 1,
+        use_start_delay: false,
+        use_duration: false,
+    }
+}
+
+#[test]
+fn save_replaces_profile_with_same_name() {
+    let mut profiles = Vec::new();
+    save(&mut profiles, profile("fast", 20));
+    save(&mut profiles, profile("slow", 500));
+    save(&mut profiles, profile("fast", 50));
+
+    assert_eq!(
+        vec![profile("fast", 50), profile("slow", 500)],
+        profiles,
+        "Should keep the order and replace the saved profile"
+    );
+}
+
+#[test]
+fn next_wraps_around() {
+    let profiles = vec![profile("a", 20), profile("b", 30), profile("c", 40)];
+
+    assert_eq!(Some(&profiles[1]), next(&profiles, "a"));
+    assert_eq!(Some(&profiles[2]), next(&profiles, "b"));
+    assert_eq!(
+        Some(&profiles[0]),
+        next(&profiles, "c"),
+        "Should start over after the last profile"
+    );
+    assert_eq!(
+        Some(&profiles[0]),
+        next(&profiles, ""),
+        "Should start with the first profile without an active one"
+    );
+    assert_eq!(None, next(&[], "a"), "Should have no profile to switch to");
+}
+
+#[test]
+fn clamp_limits_values() {
+    let mut clamped = Profile {
+        start_delay: 0,
+        duration: 999,
+        ..profile("a", 0)
+    };
+    clamped.clamp();
+    assert_eq!(20, clamped.delay);
+    assert_eq!(1, clamped.start_delay);
+    assert_eq!(60, clamped.duration);
+}
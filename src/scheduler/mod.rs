@@ -0,0 +1,228 @@
+use chrono::{
+    Datelike, Duration as ChronoDuration, Local, NaiveDate, NaiveDateTime, NaiveTime, Weekday,
+};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::sleep;
+
+#[cfg(test)]
+mod test;
+
+/// How often the scheduler checks for due jobs.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Runs that were missed by more than this, e.g. while the computer was suspended, are skipped.
+const MISSED_GRACE_SECS: i64 = 60;
+
+/// A run of the autoclicker that is started at scheduled times.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Job {
+    /// When to start, e.g. "every weekday 09:00" or "at 2026-10-20 09:00".
+    pub schedule: String,
+    /// How long to click in seconds, None uses the duration of the settings.
+    #[serde(default)]
+    pub duration: Option<u64>,
+    /// The name of the profile to load before clicking, empty keeps the current settings.
+    #[serde(default)]
+    pub profile: String,
+}
+
+/// The parsed form of a job's schedule.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Schedule {
+    /// Run once at the given local time.
+    Once(NaiveDateTime),
+    /// Run at the given local time on each of the days.
+    Weekly { days: Vec<Weekday>, time: NaiveTime },
+}
+
+impl FromStr for Schedule {
+    type Err = String;
+
+    fn from_str(schedule: &str) -> Result<Self, Self::Err> {
+        let lower = schedule.to_lowercase();
+        match lower.split_whitespace().collect::<Vec<_>>()[..] {
+            ["at", date, time] => {
+                let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                    .map_err(|_| format!("\"{date}\" is not a date like 2026-10-20"))?;
+                Ok(Schedule::Once(date.and_time(parse_time(time)?)))
+            }
+            ["every", days, time] => Ok(Schedule::Weekly {
+                days: parse_days(days)?,
+                time: parse_time(time)?,
+            }),
+            _ => Err(format!(
+                "\"{schedule}\" is not a schedule like \"every weekday 09:00\" or \"at 2026-10-20 09:00\""
+            )),
+        }
+    }
+}
+
+impl Schedule {
+    /// The first time the schedule runs after `now`, None if it never runs again.
+    pub fn next_after(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        match self {
+            Schedule::Once(time) => (*time > now).then_some(*time),
+            Schedule::Weekly { days, time } => (0..=7)
+                .map(|offset| now.date() + ChronoDuration::days(offset))
+                .filter(|date| days.contains(&date.weekday()))
+                .map(|date| date.and_time(*time))
+                .find(|run| *run > now),
+        }
+    }
+}
+
+fn parse_time(time: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(time, "%H:%M")
+        .map_err(|_| format!("\"{time}\" is not a time like 09:00"))
+}
+
+/// Parse "day", "weekday", "weekend" or a list of days like "mon,wed,fri".
+fn parse_days(days: &str) -> Result<Vec<Weekday>, String> {
+    use Weekday::*;
+    match days {
+        "day" => Ok(vec![Mon, Tue, Wed, Thu, Fri, Sat, Sun]),
+        "weekday" => Ok(vec![Mon, Tue, Wed, Thu, Fri]),
+        "weekend" => Ok(vec![Sat, Sun]),
+        days => days
+            .split(',')
+            .map(|day| {
+                Weekday::from_str(day).map_err(|_| format!("\"{day}\" is not a day of the week"))
+            })
+            .collect(),
+    }
+}
+
+/// The source of the current local time, so the scheduler can be tested without waiting.
+pub trait Clock: Send {
+    fn now(&self) -> NaiveDateTime;
+}
+
+/// The local time of the system.
+pub struct LocalClock;
+
+impl Clock for LocalClock {
+    fn now(&self) -> NaiveDateTime {
+        Local::now().naive_local()
+    }
+}
+
+/// A job and the next time it runs.
+struct Entry {
+    job: Job,
+    schedule: Option<Schedule>,
+    next: Option<NaiveDateTime>,
+}
+
+/// Keeps track of when the jobs are due.
+/// The time is checked by polling, so changes of the system time and suspend are handled.
+pub struct Scheduler {
+    clock: Box<dyn Clock>,
+    entries: Vec<Entry>,
+}
+
+impl Scheduler {
+    /// Create a scheduler without jobs.
+    pub fn new<C>(clock: C) -> Self
+    where
+        C: Clock + 'static,
+    {
+        Self {
+            clock: Box::new(clock),
+            entries: Vec::new(),
+        }
+    }
+
+    /// Replace the jobs, their next runs are counted from now.
+    /// Jobs with an invalid schedule are kept, but never run.
+    pub fn set_jobs(&mut self, jobs: Vec<Job>) {
+        let now = self.clock.now();
+        self.entries = jobs
+            .into_iter()
+            .map(|job| {
+                let schedule = match job.schedule.parse::<Schedule>() {
+                    Ok(schedule) => Some(schedule),
+                    Err(e) => {
+                        eprintln!("Not scheduling job: {e}");
+                        None
+                    }
+                };
+                let next = schedule
+                    .as_ref()
+                    .and_then(|schedule| schedule.next_after(now));
+                Entry {
+                    job,
+                    schedule,
+                    next,
+                }
+            })
+            .collect();
+    }
+
+    /// Return the jobs that are due now and schedule their next runs.
+    pub fn due(&mut self) -> Vec<Job> {
+        let now = self.clock.now();
+        let mut due = Vec::new();
+        for entry in &mut self.entries {
+            let Some(next) = entry.next.filter(|next| *next <= now) else {
+                continue;
+            };
+            if (now - next).num_seconds() <= MISSED_GRACE_SECS {
+                due.push(entry.job.clone());
+            } else {
+                eprintln!(
+                    "Skipping missed run of \"{}\" at {next}",
+                    entry.job.schedule
+                );
+            }
+            entry.next = entry
+                .schedule
+                .as_ref()
+                .and_then(|schedule| schedule.next_after(now));
+        }
+        due
+    }
+
+    /// The next run of every job, in the order of the jobs.
+    pub fn next_runs(&self) -> Vec<Option<NaiveDateTime>> {
+        self.entries.iter().map(|entry| entry.next).collect()
+    }
+
+    /// The earliest next run of all jobs.
+    pub fn next_run(&self) -> Option<NaiveDateTime> {
+        self.entries.iter().filter_map(|entry| entry.next).min()
+    }
+}
+
+/// Check the scheduler for due jobs every `poll` and pass them to `on_due`.
+/// `on_change` is called after jobs ran, so the next runs can be shown.
+pub fn run<F, G>(scheduler: Arc<Mutex<Scheduler>>, poll: Duration, on_due: F, on_change: G)
+where
+    F: Fn(Job) + Send + 'static,
+    G: Fn() + Send + 'static,
+{
+    tokio::spawn(async move {
+        loop {
+            sleep(poll).await;
+            let due = scheduler.lock().unwrap().due();
+            if due.is_empty() {
+                continue;
+            }
+            for job in due {
+                println!("Starting scheduled job \"{}\"", job.schedule);
+                on_due(job);
+            }
+            on_change();
+        }
+    });
+}
+
+/// Describe when a job runs next for the UI.
+pub fn describe_next_run(next: Option<NaiveDateTime>) -> String {
+    match next {
+        Some(next) => next.format("%a %Y-%m-%d %H:%M").to_string(),
+        None => "never".to_string(),
+    }
+}
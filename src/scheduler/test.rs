@@ -0,0 +1,193 @@
+use super::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A clock that only moves when the test sets it.
+#[derive(Clone)]
+struct MockClock(Arc<Mutex<NaiveDateTime>>);
+
+impl MockClock {
+    fn at(time: &str) -> Self {
+        Self(Arc::new(Mutex::new(datetime(time))))
+    }
+
+    fn set(&self, time: &str) {
+        *self.0.lock().unwrap() = datetime(time);
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> NaiveDateTime {
+        *self.0.lock().unwrap()
+    }
+}
+
+fn datetime(time: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M:%S").expect("Should be a valid time")
+}
+
+fn job(schedule: &str) -> Job {
+    Job {
+        schedule: schedule.to_string(),
+        duration: Some(30),
+        profile: String::new(),
+    }
+}
+
+#[test]
+fn parse_schedules() {
+    let nine = NaiveTime::from_hms_opt(9, 0, 0).unwrap();
+    assert_eq!(
+        Ok(Schedule::Weekly {
+            days: vec![
+                Weekday::Mon,
+                Weekday::Tue,
+                Weekday::Wed,
+                Weekday::Thu,
+                Weekday::Fri
+            ],
+            time: nine
+        }),
+        "every weekday 09:00".parse()
+    );
+    assert_eq!(
+        Ok(Schedule::Weekly {
+            days: vec![Weekday::Mon, Weekday::Fri],
+            time: nine
+        }),
+        "Every Mon,friday 09:00".parse()
+    );
+    assert_eq!(
+        Ok(Schedule::Once(datetime("2026-10-20 09:00:00"))),
+        "at 2026-10-20 09:00".parse()
+    );
+
+    for invalid in [
+        "",
+        "every weekday",
+        "every someday 09:00",
+        "every day 25:00",
+        "at tomorrow 09:00",
+        "daily at 09:00",
+    ] {
+        assert!(
+            invalid.parse::<Schedule>().is_err(),
+            "\"{invalid}\" should be rejected"
+        );
+    }
+}
+
+#[test]
+fn next_after_skips_to_the_next_matching_day() {
+    let schedule: Schedule = "every weekday 09:00".parse().unwrap();
+    // 2026-10-16 is a Friday.
+    assert_eq!(
+        Some(datetime("2026-10-16 09:00:00")),
+        schedule.next_after(datetime("2026-10-16 08:00:00"))
+    );
+    assert_eq!(
+        Some(datetime("2026-10-19 09:00:00")),
+        schedule.next_after(datetime("2026-10-16 09:00:00")),
+        "Should skip the weekend"
+    );
+
+    let once: Schedule = "at 2026-10-20 09:00".parse().unwrap();
+    assert_eq!(None, once.next_after(datetime("2026-10-20 09:00:00")));
+}
+
+#[test]
+fn scheduler_reports_due_jobs_once() {
+    let clock = MockClock::at("2026-10-16 08:59:00");
+    let mut scheduler = Scheduler::new(clock.clone());
+    scheduler.set_jobs(vec![job("every day 09:00"), job("at 2026-10-16 10:00")]);
+    assert_eq!(Some(datetime("2026-10-16 09:00:00")), scheduler.next_run());
+
+    assert!(scheduler.due().is_empty(), "Nothing should be due yet");
+    clock.set("2026-10-16 09:00:01");
+    assert_eq!(vec![job("every day 09:00")], scheduler.due());
+    assert!(scheduler.due().is_empty(), "A job should only run once");
+    assert_eq!(
+        vec![
+            Some(datetime("2026-10-17 09:00:00")),
+            Some(datetime("2026-10-16 10:00:00"))
+        ],
+        scheduler.next_runs()
+    );
+
+    clock.set("2026-10-16 10:00:00");
+    assert_eq!(vec![job("at 2026-10-16 10:00")], scheduler.due());
+    assert_eq!(
+        None,
+        scheduler.next_runs()[1],
+        "A one-time job should not run again"
+    );
+}
+
+#[test]
+fn scheduler_skips_missed_runs() {
+    let clock = MockClock::at("2026-10-16 08:00:00");
+    let mut scheduler = Scheduler::new(clock.clone());
+    scheduler.set_jobs(vec![job("every day 09:00")]);
+
+    // The computer was suspended over the scheduled time.
+    clock.set("2026-10-16 11:00:00");
+    assert!(scheduler.due().is_empty(), "Missed run should be skipped");
+    assert_eq!(Some(datetime("2026-10-17 09:00:00")), scheduler.next_run());
+}
+
+#[test]
+fn scheduler_keeps_invalid_jobs_without_running_them() {
+    let clock = MockClock::at("2026-10-16 08:00:00");
+    let mut scheduler = Scheduler::new(clock);
+    scheduler.set_jobs(vec![job("sometimes"), job("every day 09:00")]);
+    assert_eq!(
+        vec![None, Some(datetime("2026-10-16 09:00:00"))],
+        scheduler.next_runs()
+    );
+}
+
+#[tokio::test]
+async fn run_starts_due_jobs() {
+    let clock = MockClock::at("2026-10-16 08:59:59");
+    let scheduler = Arc::new(Mutex::new(Scheduler::new(clock.clone())));
+    scheduler
+        .lock()
+        .unwrap()
+        .set_jobs(vec![job("every day 09:00")]);
+    let started = Arc::new(AtomicUsize::new(0));
+    let changed = Arc::new(AtomicUsize::new(0));
+
+    let started_jobs = Arc::clone(&started);
+    let changes = Arc::clone(&changed);
+    run(
+        Arc::clone(&scheduler),
+        Duration::from_millis(10),
+        move |job| {
+            assert_eq!(Some(30), job.duration);
+            started_jobs.fetch_add(1, Ordering::SeqCst);
+        },
+        move || {
+            changes.fetch_add(1, Ordering::SeqCst);
+        },
+    );
+
+    sleep(Duration::from_millis(50)).await;
+    assert_eq!(
+        0,
+        started.load(Ordering::SeqCst),
+        "Job should not run early"
+    );
+
+    clock.set("2026-10-16 09:00:00");
+    sleep(Duration::from_millis(50)).await;
+    assert_eq!(1, started.load(Ordering::SeqCst), "Job should run once");
+    assert_eq!(1, changed.load(Ordering::SeqCst));
+}
+
+#[test]
+fn describe_next_run_for_ui() {
+    assert_eq!(
+        "Fri 2026-10-16 09:00",
+        describe_next_run(Some(datetime("2026-10-16 09:00:00")))
+    );
+    assert_eq!("never", describe_next_run(None));
+}
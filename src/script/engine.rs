@@ -0,0 +1,167 @@
+use super::executor::press_keys;
+use super::parser::{parse_button, parse_keys};
+use crate::autoclicker::SharedInput;
+use crate::input::{InputBackend, InputResult};
+use enigo::{Button, Direction};
+use rhai::{AST, Dynamic, Engine, EvalAltResult, Map, Position};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Limits that keep a script from exhausting the memory or stack of the app.
+const MAX_CALL_LEVELS: usize = 64;
+const MAX_EXPR_DEPTH: usize = 64;
+const MAX_STRING_SIZE: usize = 10_000;
+const MAX_COLLECTION_SIZE: usize = 10_000;
+
+/// A compiled Rhai script.
+/// Scripts can only control the virtual input, they have no access to files or other programs.
+#[derive(Debug, Clone)]
+pub struct RhaiScript {
+    ast: AST,
+}
+
+impl RhaiScript {
+    /// Compile the script, returning the first syntax error with its line.
+    pub fn compile(text: &str) -> Result<Self, String> {
+        let ast = sandboxed_engine()
+            .compile(text)
+            .map_err(|e| e.to_string())?;
+        Ok(Self { ast })
+    }
+
+    /// Run the script until it is done or running is false.
+    /// Clicks of the `excluded` button are skipped, because it triggers the autoclicker.
+    /// Blocks the thread, so it needs to be run with `spawn_blocking`.
+    pub fn run(
+        &self,
+        input: SharedInput,
+        running: Arc<AtomicBool>,
+        excluded: Option<Button>,
+    ) -> Result<(), String> {
+        let engine = script_engine(input, Arc::clone(&running), excluded);
+        match engine.run_ast(&self.ast) {
+            Ok(()) => Ok(()),
+            // Stopping the autoclicker terminates the script.
+            Err(e) if matches!(*e, EvalAltResult::ErrorTerminated(..)) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+/// An engine with the limits of the sandbox, but without the functions to control the input.
+fn sandboxed_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_max_call_levels(MAX_CALL_LEVELS)
+        .set_max_expr_depths(MAX_EXPR_DEPTH, MAX_EXPR_DEPTH)
+        .set_max_string_size(MAX_STRING_SIZE)
+        .set_max_array_size(MAX_COLLECTION_SIZE)
+        .set_max_map_size(MAX_COLLECTION_SIZE)
+        .disable_symbol("eval");
+    engine
+}
+
+/// An engine with the functions scripts use to control the input.
+fn script_engine(input: SharedInput, running: Arc<AtomicBool>, excluded: Option<Button>) -> Engine {
+    let mut engine = sandboxed_engine();
+    let start = Instant::now();
+
+    let stop = Arc::clone(&running);
+    engine.on_progress(move |_| match stop.load(Ordering::Acquire) {
+        true => None,
+        false => Some(Dynamic::UNIT),
+    });
+
+    let is_running = Arc::clone(&running);
+    engine.register_fn("is_running", move || is_running.load(Ordering::Acquire));
+    engine.register_fn("elapsed_ms", move || start.elapsed().as_millis() as i64);
+
+    let sleeping = Arc::clone(&running);
+    engine.register_fn("sleep", move |ms: i64| sleep(ms, &sleeping));
+
+    let (clicker, active) = (Arc::clone(&input), Arc::clone(&running));
+    engine.register_fn("click", move || {
+        if excluded == Some(Button::Left) {
+            return Ok(());
+        }
+        with_input(&clicker, &active, |input| {
+            input.button(Button::Left, Direction::Click)
+        })
+    });
+    let (clicker, active) = (Arc::clone(&input), Arc::clone(&running));
+    engine.register_fn("click", move |button: &str| {
+        let button = parse_button(button)?;
+        if excluded == Some(button) {
+            return Ok(());
+        }
+        with_input(&clicker, &active, |input| {
+            input.button(button, Direction::Click)
+        })
+    });
+
+    let (mover, active) = (Arc::clone(&input), Arc::clone(&running));
+    engine.register_fn("move", move |x: i64, y: i64| {
+        with_input(&mover, &active, |input| input.move_to(x as i32, y as i32))
+    });
+    let (mover, active) = (Arc::clone(&input), Arc::clone(&running));
+    engine.register_fn("move_by", move |dx: i64, dy: i64| {
+        with_input(&mover, &active, |input| input.move_by(dx as i32, dy as i32))
+    });
+
+    let (keyboard, active) = (Arc::clone(&input), Arc::clone(&running));
+    engine.register_fn("key", move |keys: &str| {
+        let codes = parse_keys(keys)?;
+        with_input(&keyboard, &active, |input| press_keys(input, &codes))
+    });
+
+    engine.register_fn("position", move || -> Result<Map, Box<EvalAltResult>> {
+        let mut guard = input.blocking_lock();
+        let input = guard
+            .as_mut()
+            .ok_or("virtual input is no longer available")?;
+        let (x, y) = input.location().map_err(|e| e.to_string())?;
+        let mut position = Map::new();
+        position.insert("x".into(), (x as i64).into());
+        position.insert("y".into(), (y as i64).into());
+        Ok(position)
+    });
+
+    engine
+}
+
+/// Send input through the backend, turning failures into script errors.
+/// Terminates the script if it was stopped while waiting for the input, e.g. while paused.
+fn with_input<F>(
+    input: &SharedInput,
+    running: &AtomicBool,
+    send: F,
+) -> Result<(), Box<EvalAltResult>>
+where
+    F: FnOnce(&mut dyn InputBackend) -> InputResult<()>,
+{
+    let mut guard = input.blocking_lock();
+    if !running.load(Ordering::Acquire) {
+        return Err(EvalAltResult::ErrorTerminated(Dynamic::UNIT, Position::NONE).into());
+    }
+    let input = guard
+        .as_mut()
+        .ok_or("virtual input is no longer available")?;
+    send(input.as_mut()).map_err(|e| e.to_string().into())
+}
+
+/// Sleep for the given milliseconds, waking up early once running is false.
+fn sleep(ms: i64, running: &AtomicBool) {
+    // A time too long to represent lasts until the script is stopped.
+    let end = Instant::now().checked_add(Duration::from_millis(ms.max(0) as u64));
+    while running.load(Ordering::Acquire) {
+        let left = end.map_or(Duration::MAX, |end| {
+            end.saturating_duration_since(Instant::now())
+        });
+        if left.is_zero() {
+            return;
+        }
+        thread::sleep(left.min(Duration::from_millis(10)));
+    }
+}
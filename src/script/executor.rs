@@ -0,0 +1,147 @@
+use super::{Command, Script, Statement};
+use crate::autoclicker::{SharedInput, wait};
+use crate::input::{InputBackend, InputResult};
+use enigo::{Button, Direction};
+use std::hash::{BuildHasher, RandomState};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// The position in a block of statements that is being run.
+struct Block<'a> {
+    statements: &'a [Statement],
+    next: usize,
+    /// How often the block is run again after this time.
+    repeats_left: u32,
+}
+
+impl Script {
+    /// Run the script with the virtual input, until it is done or running is false.
+    /// Clicks of the `excluded` button are skipped, because it triggers the autoclicker.
+    /// Returns an error with the line of the command that failed.
+    pub async fn run(
+        &self,
+        input: &SharedInput,
+        running: &AtomicBool,
+        excluded: Option<Button>,
+    ) -> Result<(), String> {
+        let mut blocks = vec![Block {
+            statements: &self.statements,
+            next: 0,
+            repeats_left: 0,
+        }];
+        while let Some(block) = blocks.last_mut() {
+            if !running.load(Ordering::Acquire) {
+                return Ok(());
+            }
+            let Some(statement) = block.statements.get(block.next) else {
+                if block.repeats_left > 0 {
+                    block.repeats_left -= 1;
+                    block.next = 0;
+                    // A block without waits should not keep the runtime busy.
+                    tokio::task::yield_now().await;
+                } else {
+                    blocks.pop();
+                }
+                continue;
+            };
+            block.next += 1;
+
+            match &statement.command {
+                Command::Repeat { count, body } => {
+                    if *count > 0 {
+                        blocks.push(Block {
+                            statements: body,
+                            next: 0,
+                            repeats_left: count - 1,
+                        });
+                    }
+                }
+                Command::Wait(duration) => {
+                    wait(duration.as_millis() as u64, running).await;
+                }
+                Command::RandomWait(min, max) => {
+                    wait(random_between(*min, *max).as_millis() as u64, running).await;
+                }
+                Command::Click { button, .. } if Some(*button) == excluded => (),
+                Command::Click { button, count } => {
+                    // Every click takes the input on its own, so a long series can be stopped
+                    // and the failsafe is checked in between.
+                    for _ in 0..*count {
+                        {
+                            let mut input = input.lock().await;
+                            if !running.load(Ordering::Acquire) {
+                                return Ok(());
+                            }
+                            let input = input
+                                .as_mut()
+                                .ok_or("virtual input is no longer available")?;
+                            input
+                                .button(*button, Direction::Click)
+                                .map_err(|e| format!("line {}: {e}", statement.line))?;
+                        }
+                        tokio::task::yield_now().await;
+                    }
+                }
+                command => {
+                    let mut input = input.lock().await;
+                    // The run might have been stopped while it was paused.
+                    if !running.load(Ordering::Acquire) {
+                        return Ok(());
+                    }
+                    let input = input
+                        .as_mut()
+                        .ok_or("virtual input is no longer available")?;
+                    run_input_command(command, input.as_mut())
+                        .map_err(|e| format!("line {}: {e}", statement.line))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Send the input of a command that neither waits, clicks nor contains other commands.
+fn run_input_command(command: &Command, input: &mut dyn InputBackend) -> InputResult<()> {
+    match command {
+        Command::MoveTo { x, y } => input.move_to(*x, *y),
+        Command::MoveBy { dx, dy } => input.move_by(*dx, *dy),
+        Command::Key(codes) => press_keys(input, codes),
+        Command::Click { .. }
+        | Command::Wait(_)
+        | Command::RandomWait(..)
+        | Command::Repeat { .. } => Ok(()),
+    }
+}
+
+/// Press the keys in order and release them in reverse.
+/// If a key can't be pressed, the keys pressed before it are released, so none stay held.
+pub(super) fn press_keys(input: &mut dyn InputBackend, codes: &[u16]) -> InputResult<()> {
+    for (pressed, code) in codes.iter().enumerate() {
+        if let Err(e) = input.key(*code, Direction::Press) {
+            for code in codes[..pressed].iter().rev() {
+                if let Err(e) = input.key(*code, Direction::Release) {
+                    eprintln!("Failed to release key {code}: {e}");
+                }
+            }
+            return Err(e);
+        }
+    }
+    for code in codes.iter().rev() {
+        input.key(*code, Direction::Release)?;
+    }
+    Ok(())
+}
+
+/// Return a random duration between min and max, both included, with millisecond precision.
+pub(super) fn random_between(min: Duration, max: Duration) -> Duration {
+    let min = min.as_millis() as u64;
+    let max = max.as_millis() as u64;
+    // Every RandomState is seeded with new random keys, which is random enough for waiting.
+    let random = RandomState::new().hash_one(0u8);
+    let offset = match max.saturating_sub(min).checked_add(1) {
+        Some(range) => random % range,
+        // The range covers every u64, so any value is in it.
+        None => random,
+    };
+    Duration::from_millis(min.saturating_add(offset))
+}
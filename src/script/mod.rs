@@ -0,0 +1,161 @@
+use crate::autoclicker::SharedInput;
+use enigo::Button;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+
+mod engine;
+mod executor;
+mod parser;
+
+#[cfg(test)]
+mod test;
+
+pub use engine::RhaiScript;
+
+/// The languages scripts can be written in.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Language {
+    /// The line based action format of `Script`.
+    #[default]
+    Actions,
+    Rhai,
+}
+
+impl Language {
+    /// Parse the name of the language as shown in the UI, unknown names are action scripts.
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "rhai" => Language::Rhai,
+            _ => Language::Actions,
+        }
+    }
+
+    /// The language of the script file, files ending in ".rhai" are Rhai scripts.
+    pub fn from_path<P>(path: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        match path.as_ref().extension() {
+            Some(extension) if extension == "rhai" => Language::Rhai,
+            _ => Language::Actions,
+        }
+    }
+}
+
+/// A script in one of the supported languages, ready to run.
+#[derive(Debug, Clone)]
+pub enum Program {
+    Actions(Script),
+    Rhai(RhaiScript),
+}
+
+impl Program {
+    /// Parse the script in the given language.
+    pub fn parse(language: Language, text: &str) -> Result<Self, String> {
+        match language {
+            Language::Actions => Script::parse(text)
+                .map(Program::Actions)
+                .map_err(|e| e.to_string()),
+            Language::Rhai => RhaiScript::compile(text).map(Program::Rhai),
+        }
+    }
+
+    /// Load and parse the script file, the language depends on its extension.
+    pub fn load<P>(path: P) -> Result<Self, Box<dyn Error>>
+    where
+        P: AsRef<Path>,
+    {
+        let text = fs::read_to_string(&path)?;
+        Ok(Self::parse(Language::from_path(path), &text)?)
+    }
+
+    /// Run the script with the virtual input, until it is done or running is false.
+    /// The `excluded` button is never clicked, because it triggers the autoclicker.
+    pub async fn run(
+        &self,
+        input: SharedInput,
+        running: Arc<AtomicBool>,
+        excluded: Option<Button>,
+    ) -> Result<(), String> {
+        match self {
+            Program::Actions(script) => script.run(&input, &running, excluded).await,
+            Program::Rhai(script) => {
+                let script = script.clone();
+                // Rhai scripts block while they run.
+                tokio::task::spawn_blocking(move || script.run(input, running, excluded))
+                    .await
+                    .map_err(|e| e.to_string())?
+            }
+        }
+    }
+}
+
+/// A single command of a script.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Move the pointer to the position on the screen.
+    MoveTo {
+        x: i32,
+        y: i32,
+    },
+    /// Move the pointer relative to its position.
+    MoveBy {
+        dx: i32,
+        dy: i32,
+    },
+    Click {
+        button: Button,
+        count: u32,
+    },
+    /// Press the keys in order and release them in reverse, identified by their evdev key codes.
+    Key(Vec<u16>),
+    Wait(Duration),
+    /// Wait for a random time between the two durations.
+    RandomWait(Duration, Duration),
+    Repeat {
+        count: u32,
+        body: Vec<Statement>,
+    },
+}
+
+/// A command and the line of the script it was read from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statement {
+    pub line: usize,
+    pub command: Command,
+}
+
+/// A parsed action script.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Script {
+    pub statements: Vec<Statement>,
+}
+
+impl Script {
+    /// Parse a script from its text.
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        Ok(Self {
+            statements: parser::parse(text)?,
+        })
+    }
+}
+
+/// The reason a script could not be parsed and the line it was found in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for ParseError {}
@@ -0,0 +1,200 @@
+use super::{Command, ParseError, Statement};
+use enigo::Button;
+use evdev::KeyCode;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// The longest a wait can last, so the milliseconds to wait always fit into a u64.
+const MAX_WAIT: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Parse the text of a script into its statements.
+/// Everything after a `#` is a comment, blocks are closed by a `}` on its own line.
+pub(super) fn parse(text: &str) -> Result<Vec<Statement>, ParseError> {
+    // The statements of the top level and every open block, with the line and count of the repeat.
+    let mut blocks: Vec<(usize, u32, Vec<Statement>)> = vec![(0, 1, Vec::new())];
+    for (index, line) in text.lines().enumerate() {
+        let number = index + 1;
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let error = |message: String| ParseError {
+            line: number,
+            message,
+        };
+
+        if line == "}" {
+            if blocks.len() == 1 {
+                return Err(error("\"}\" without an open block".to_string()));
+            }
+            let (line, count, body) = blocks.pop().unwrap_or_default();
+            push(&mut blocks, line, Command::Repeat { count, body });
+            continue;
+        }
+        if let Some(head) = line.strip_suffix('{') {
+            let count = match head.split_whitespace().collect::<Vec<_>>()[..] {
+                ["repeat", count] => parse_number(count).map_err(error)?,
+                _ => {
+                    return Err(error(format!(
+                        "only repeat can open a block, got \"{line}\""
+                    )));
+                }
+            };
+            blocks.push((number, count, Vec::new()));
+            continue;
+        }
+        let command = parse_command(line).map_err(error)?;
+        push(&mut blocks, number, command);
+    }
+
+    let (line, _, statements) = blocks.pop().unwrap_or_default();
+    if !blocks.is_empty() {
+        return Err(ParseError {
+            line,
+            message: "repeat is missing its closing \"}\"".to_string(),
+        });
+    }
+    Ok(statements)
+}
+
+fn push(blocks: &mut [(usize, u32, Vec<Statement>)], line: usize, command: Command) {
+    if let Some((_, _, statements)) = blocks.last_mut() {
+        statements.push(Statement { line, command });
+    }
+}
+
+/// Parse a single line that is not part of a block.
+fn parse_command(line: &str) -> Result<Command, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    match words[..] {
+        ["move", x, y] => Ok(Command::MoveTo {
+            x: parse_number(x)?,
+            y: parse_number(y)?,
+        }),
+        ["move-by", dx, dy] => Ok(Command::MoveBy {
+            dx: parse_number(dx)?,
+            dy: parse_number(dy)?,
+        }),
+        ["click"] => Ok(Command::Click {
+            button: Button::Left,
+            count: 1,
+        }),
+        ["click", button] => Ok(Command::Click {
+            button: parse_button(button)?,
+            count: 1,
+        }),
+        ["click", button, count] => Ok(Command::Click {
+            button: parse_button(button)?,
+            count: parse_count(count)?,
+        }),
+        ["key", keys] => Ok(Command::Key(parse_keys(keys)?)),
+        ["wait", duration] => Ok(Command::Wait(parse_duration(duration)?)),
+        ["random-wait", range] => {
+            let (min, max) = parse_range(range)?;
+            Ok(Command::RandomWait(min, max))
+        }
+        ["repeat", ..] => Err("repeat needs a block, e.g. \"repeat 10 {\"".to_string()),
+        [command, ..] => match command {
+            "move" | "move-by" | "click" | "key" | "wait" | "random-wait" => Err(format!(
+                "wrong number of arguments for {command} in \"{line}\""
+            )),
+            _ => Err(format!("unknown command \"{command}\"")),
+        },
+        [] => Err("missing command".to_string()),
+    }
+}
+
+fn parse_number<T>(value: &str) -> Result<T, String>
+where
+    T: FromStr,
+{
+    value
+        .parse()
+        .map_err(|_| format!("\"{value}\" is not a valid number"))
+}
+
+pub(super) fn parse_button(name: &str) -> Result<Button, String> {
+    match name {
+        "left" => Ok(Button::Left),
+        "right" => Ok(Button::Right),
+        "middle" => Ok(Button::Middle),
+        "back" => Ok(Button::Back),
+        "forward" => Ok(Button::Forward),
+        _ => Err(format!(
+            "unknown button \"{name}\", expected left, right, middle, back or forward"
+        )),
+    }
+}
+
+/// Parse a click count like "x2".
+fn parse_count(count: &str) -> Result<u32, String> {
+    let parsed = count
+        .strip_prefix('x')
+        .and_then(|count| count.parse().ok())
+        .filter(|count| *count > 0);
+    parsed.ok_or_else(|| format!("\"{count}\" is not a click count like x2"))
+}
+
+/// Parse a key combination like "ctrl+s" into the evdev key codes to press in order.
+pub(super) fn parse_keys(keys: &str) -> Result<Vec<u16>, String> {
+    keys.split('+')
+        .map(|key| {
+            let name = match key.to_uppercase().as_str() {
+                "CTRL" | "CONTROL" => "LEFTCTRL".to_string(),
+                "SHIFT" => "LEFTSHIFT".to_string(),
+                "ALT" => "LEFTALT".to_string(),
+                "SUPER" | "LOGO" | "META" => "LEFTMETA".to_string(),
+                "RETURN" => "ENTER".to_string(),
+                "ESCAPE" => "ESC".to_string(),
+                name => name.to_string(),
+            };
+            KeyCode::from_str(&format!("KEY_{name}"))
+                .map(|key| key.code())
+                .map_err(|_| format!("unknown key \"{key}\" in \"{keys}\""))
+        })
+        .collect()
+}
+
+/// Parse a duration like "150ms" or "2s".
+fn parse_duration(duration: &str) -> Result<Duration, String> {
+    let (value, unit) = split_unit(duration);
+    let value: u64 = parse_number(value)?;
+    let parsed = match unit {
+        "ms" => Duration::from_millis(value),
+        "s" => Duration::from_secs(value),
+        _ => {
+            return Err(format!(
+                "\"{duration}\" needs a unit of ms or s, e.g. 150ms"
+            ));
+        }
+    };
+    if parsed > MAX_WAIT {
+        return Err(format!("\"{duration}\" is longer than a day"));
+    }
+    Ok(parsed)
+}
+
+/// Parse a range of durations like "100..300ms", the unit of the end applies to both if the start has none.
+fn parse_range(range: &str) -> Result<(Duration, Duration), String> {
+    let (start, end) = range
+        .split_once("..")
+        .ok_or_else(|| format!("\"{range}\" is not a range like 100..300ms"))?;
+    let end_unit = split_unit(end).1;
+    let start = match split_unit(start).1 {
+        "" => parse_duration(&format!("{start}{end_unit}"))?,
+        _ => parse_duration(start)?,
+    };
+    let end = parse_duration(end)?;
+    if start > end {
+        return Err(format!("the start of \"{range}\" is after its end"));
+    }
+    Ok((start, end))
+}
+
+/// Split a value like "150ms" into its number and unit.
+fn split_unit(value: &str) -> (&str, &str) {
+    let unit_start = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    value.split_at(unit_start)
+}
@@ -0,0 +1,389 @@
+use super::executor::random_between;
+use super::*;
+use crate::autoclicker::SharedInput;
+use crate::input::recording::{InputEvent, RecordingBackend};
+use enigo::Direction;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+use tokio::sync::Mutex;
+
+fn commands(script: &Script) -> Vec<Command> {
+    script
+        .statements
+        .iter()
+        .map(|statement| statement.command.clone())
+        .collect()
+}
+
+fn shared_input(backend: &RecordingBackend) -> SharedInput {
+    Arc::new(Mutex::new(Some(Box::new(backend.clone()))))
+}
+
+#[test]
+fn parse_all_commands() {
+    let script = Script::parse(
+        "# Farm the button
+        move 100 200
+        move-by -5 10
+        click
+        click right x2
+        key ctrl+s   # save
+        wait 150ms
+        wait 2s
+        random-wait 100..300ms
+        random-wait 1s..2s
+        ",
+    )
+    .expect("Should parse script");
+
+    assert_eq!(
+        vec![
+            Command::MoveTo { x: 100, y: 200 },
+            Command::MoveBy { dx: -5, dy: 10 },
+            Command::Click {
+                button: Button::Left,
+                count: 1
+            },
+            Command::Click {
+                button: Button::Right,
+                count: 2
+            },
+            Command::Key(vec![29, 31]),
+            Command::Wait(Duration::from_millis(150)),
+            Command::Wait(Duration::from_secs(2)),
+            Command::RandomWait(Duration::from_millis(100), Duration::from_millis(300)),
+            Command::RandomWait(Duration::from_secs(1), Duration::from_secs(2)),
+        ],
+        commands(&script)
+    );
+    assert_eq!(2, script.statements[0].line, "Should keep the line number");
+}
+
+#[test]
+fn parse_nested_repeat() {
+    let script = Script::parse(
+        "repeat 3 {
+            click
+            repeat 2 {
+                wait 10ms
+            }
+        }
+        click right",
+    )
+    .expect("Should parse script");
+
+    let Command::Repeat { count, body } = &script.statements[0].command else {
+        panic!("First command should be a repeat");
+    };
+    assert_eq!(3, *count);
+    assert_eq!(2, body.len(), "Repeat should contain its block");
+    assert_eq!(3, body[1].line);
+    assert!(matches!(body[1].command, Command::Repeat { count: 2, .. }));
+    assert_eq!(7, script.statements[1].line);
+}
+
+#[test]
+fn parse_errors_have_line_numbers() {
+    let tests = [
+        ("click\njump 1 2", 2, "unknown command"),
+        ("click middle x0", 1, "click count"),
+        ("\nclick wheel", 2, "unknown button"),
+        ("wait 150", 1, "needs a unit"),
+        ("random-wait 300..100ms", 1, "after its end"),
+        ("wait 86401s", 1, "longer than a day"),
+        (
+            "random-wait 1..18446744073709551615ms",
+            1,
+            "longer than a day",
+        ),
+        ("key ctrl+nope", 1, "unknown key"),
+        ("move 100", 1, "wrong number of arguments"),
+        ("click\nrepeat 2 {\nclick", 2, "missing its closing"),
+        ("click\n}", 2, "without an open block"),
+        ("repeat 2", 1, "needs a block"),
+    ];
+    for (text, line, message) in tests {
+        let error = Script::parse(text).expect_err(&format!("\"{text}\" should fail"));
+        assert_eq!(line, error.line, "Wrong line for \"{text}\": {error}");
+        assert!(
+            error.message.contains(message),
+            "Error for \"{text}\" should contain \"{message}\": {error}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn run_sends_input_in_order() {
+    let backend = RecordingBackend::new();
+    let script = Script::parse(
+        "move 100 200
+        repeat 2 {
+            click left x2
+        }
+        key ctrl+s",
+    )
+    .expect("Should parse script");
+
+    script
+        .run(&shared_input(&backend), &AtomicBool::new(true), None)
+        .await
+        .expect("Should run script");
+
+    let click = InputEvent::Button(Button::Left, Direction::Click);
+    assert_eq!(
+        vec![
+            InputEvent::Move(100, 200),
+            click,
+            click,
+            click,
+            click,
+            InputEvent::Key(29, Direction::Press),
+            InputEvent::Key(31, Direction::Press),
+            InputEvent::Key(31, Direction::Release),
+            InputEvent::Key(29, Direction::Release),
+        ],
+        backend.events()
+    );
+}
+
+#[tokio::test]
+async fn run_skips_excluded_button() {
+    let backend = RecordingBackend::new();
+    let script = Script::parse("click back x2\nclick right").expect("Should parse script");
+
+    script
+        .run(
+            &shared_input(&backend),
+            &AtomicBool::new(true),
+            Some(Button::Back),
+        )
+        .await
+        .expect("Should run script");
+
+    assert_eq!(
+        vec![InputEvent::Button(Button::Right, Direction::Click)],
+        backend.events(),
+        "Should not click the button that triggers the autoclicker"
+    );
+}
+
+#[tokio::test]
+async fn run_stops_during_wait() {
+    let backend = RecordingBackend::new();
+    let script = Script::parse("wait 5s\nclick").expect("Should parse script");
+    let running = Arc::new(AtomicBool::new(true));
+
+    let stop = Arc::clone(&running);
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        stop.store(false, Ordering::Release);
+    });
+    let start = Instant::now();
+    script
+        .run(&shared_input(&backend), &running, None)
+        .await
+        .expect("Should run script");
+
+    assert!(
+        start.elapsed() < Duration::from_secs(1),
+        "Stopping should interrupt the wait"
+    );
+    assert!(backend.events().is_empty(), "Should not click after stop");
+}
+
+#[tokio::test]
+async fn run_stops_during_click_series() {
+    let backend = RecordingBackend::new();
+    let script = Script::parse("click left x4000000000").expect("Should parse script");
+    let running = Arc::new(AtomicBool::new(true));
+
+    let stop = Arc::clone(&running);
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        stop.store(false, Ordering::Release);
+    });
+    script
+        .run(&shared_input(&backend), &running, None)
+        .await
+        .expect("Should run script");
+
+    assert!(
+        backend.clicks(Button::Left).len() < 4_000_000_000,
+        "Stopping should interrupt the clicks"
+    );
+}
+
+#[tokio::test]
+async fn run_fails_without_input() {
+    let script = Script::parse("click").expect("Should parse script");
+    let res = script
+        .run(&Arc::new(Mutex::new(None)), &AtomicBool::new(true), None)
+        .await;
+    assert!(res.is_err(), "Should fail without input");
+}
+
+#[test]
+fn random_between_stays_in_range() {
+    let min = Duration::from_millis(100);
+    let max = Duration::from_millis(110);
+    for _ in 0..100 {
+        let random = random_between(min, max);
+        assert!((min..=max).contains(&random), "{random:?} is out of range");
+    }
+    assert_eq!(min, random_between(min, min));
+
+    let max = Duration::from_millis(u64::MAX);
+    assert!(
+        random_between(Duration::ZERO, max) <= max,
+        "The whole range should not overflow"
+    );
+}
+
+#[tokio::test]
+async fn failed_key_releases_pressed_keys() {
+    let backend = RecordingBackend::new();
+    backend.fail_key(42);
+    let script = Script::parse("key ctrl+shift+s").expect("Should parse script");
+
+    let res = script
+        .run(&shared_input(&backend), &AtomicBool::new(true), None)
+        .await;
+    assert!(res.is_err(), "Failed key should stop the script");
+    assert_eq!(
+        vec![
+            InputEvent::Key(29, Direction::Press),
+            InputEvent::Key(29, Direction::Release),
+        ],
+        backend.events(),
+        "Pressed keys should be released"
+    );
+}
+
+async fn run_rhai(backend: &RecordingBackend, text: &str) -> Result<(), String> {
+    let program = Program::parse(Language::Rhai, text)?;
+    program
+        .run(shared_input(backend), Arc::new(AtomicBool::new(true)), None)
+        .await
+}
+
+#[tokio::test]
+async fn rhai_controls_input() {
+    let backend = RecordingBackend::new();
+    run_rhai(
+        &backend,
+        r#"
+        let pos = position();
+        if pos.x == 0 && elapsed_ms() < 10000 {
+            move(100, 200);
+        }
+        for i in 0..3 {
+            click(if i == 2 { "right" } else { "left" });
+        }
+        move_by(-5, 5);
+        key("ctrl+s");
+        "#,
+    )
+    .await
+    .expect("Should run script");
+
+    assert_eq!(
+        vec![
+            InputEvent::Move(100, 200),
+            InputEvent::Button(Button::Left, Direction::Click),
+            InputEvent::Button(Button::Left, Direction::Click),
+            InputEvent::Button(Button::Right, Direction::Click),
+            InputEvent::Move(-5, 5),
+            InputEvent::Key(29, Direction::Press),
+            InputEvent::Key(31, Direction::Press),
+            InputEvent::Key(31, Direction::Release),
+            InputEvent::Key(29, Direction::Release),
+        ],
+        backend.events()
+    );
+}
+
+#[tokio::test]
+async fn rhai_failed_key_releases_pressed_keys() {
+    let backend = RecordingBackend::new();
+    backend.fail_key(42);
+
+    let res = run_rhai(&backend, r#"key("ctrl+shift+s");"#).await;
+    assert!(res.is_err(), "Failed key should stop the script");
+    assert_eq!(
+        vec![
+            InputEvent::Key(29, Direction::Press),
+            InputEvent::Key(29, Direction::Release),
+        ],
+        backend.events(),
+        "Pressed keys should be released"
+    );
+}
+
+#[tokio::test]
+async fn rhai_skips_excluded_button() {
+    let backend = RecordingBackend::new();
+    let program = Program::parse(Language::Rhai, r#"click("middle"); click("right");"#)
+        .expect("Should compile");
+    program
+        .run(
+            shared_input(&backend),
+            Arc::new(AtomicBool::new(true)),
+            Some(Button::Middle),
+        )
+        .await
+        .expect("Should run script");
+
+    assert_eq!(
+        vec![InputEvent::Button(Button::Right, Direction::Click)],
+        backend.events(),
+        "Should not click the button that triggers the autoclicker"
+    );
+}
+
+#[tokio::test]
+async fn rhai_reports_errors() {
+    let backend = RecordingBackend::new();
+    let error =
+        Program::parse(Language::Rhai, "click();\nlet x = ;").expect_err("Should not compile");
+    assert!(error.contains("line 2"), "Should show the line: {error}");
+
+    let error = run_rhai(&backend, r#"click("wheel")"#)
+        .await
+        .expect_err("Unknown button should fail");
+    assert!(error.contains("unknown button"), "Got: {error}");
+
+    assert!(
+        Program::parse(Language::Rhai, r#"eval("click()")"#).is_err(),
+        "eval should be disabled"
+    );
+}
+
+#[tokio::test]
+async fn rhai_stops_when_autoclicker_stops() {
+    let backend = RecordingBackend::new();
+    let program = Program::parse(Language::Rhai, "while true { click(); sleep(1000); }")
+        .expect("Should compile");
+    let running = Arc::new(AtomicBool::new(true));
+
+    let stop = Arc::clone(&running);
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        stop.store(false, Ordering::Release);
+    });
+    let start = Instant::now();
+    program
+        .run(shared_input(&backend), running, None)
+        .await
+        .expect("Stopping should not be an error");
+
+    assert!(start.elapsed() < Duration::from_millis(500));
+    assert_eq!(1, backend.clicks(Button::Left).len());
+}
+
+#[test]
+fn language_from_path() {
+    assert_eq!(Language::Rhai, Language::from_path("farm.rhai"));
+    assert_eq!(Language::Actions, Language::from_path("farm.txt"));
+    assert_eq!(Language::Rhai, Language::from_name("rhai"));
+}
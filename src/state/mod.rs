@@ -1,6 +1,18 @@
-use super::slint_generatedAppWindow::{AppWindow, GlobalState};
+use super::slint_generatedAppWindow::{AppWindow, ClickProfile, GlobalState, ScheduledJob};
+use crate::autoclicker::Limits;
+use crate::failsafe::FailsafeCorner;
+use crate::hotkey::{
+    HotkeyBackendKind, HotkeyMode, HotkeySettings, HotkeyTiming, MouseTrigger, RunTiming,
+};
+use crate::idle::{IdleBackendKind, KeepaliveAction};
+use crate::input::BackendKind;
+use crate::profile::Profile;
+use crate::scheduler::Job;
+use futures_util::StreamExt;
+use inotify::{Inotify, WatchMask, Watches};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use slint::ComponentHandle;
+use slint::{ComponentHandle, Model, ModelRc, VecModel};
 use std::env;
 use std::error::Error;
 use std::fs;
@@ -15,7 +27,8 @@ pub const HOME: &str = "HOME";
 mod test;
 
 /// Contains all values from GlobalState of the UI.
-/// Defaults will be set in GlobalState in the UI.
+/// Defaults will be set in GlobalState in the UI, `State::default()` mirrors them.
+/// Settings added later need a serde default, so older state files can still be loaded.
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub struct State {
     pub delay: u64,
@@ -24,6 +37,56 @@ pub struct State {
     pub use_start_delay: bool,
     pub use_duration: bool,
     pub dark_mode: bool,
+    #[serde(default)]
+    pub input_backend: BackendKind,
+    #[serde(default)]
+    pub hotkey_mode: HotkeyMode,
+    #[serde(default)]
+    pub hotkey_backend: HotkeyBackendKind,
+    /// The X11/evdev trigger of the toggle hotkey, empty for the default.
+    #[serde(default)]
+    pub hotkey_trigger: String,
+    /// The timing of runs started by the start or toggle shortcut.
+    #[serde(default)]
+    pub hotkey_timing: HotkeyTiming,
+    /// The timing of runs started by holding the toggle shortcut in hold mode.
+    #[serde(default)]
+    pub hold_timing: HotkeyTiming,
+    #[serde(default)]
+    pub mouse_trigger: MouseTrigger,
+    #[serde(default)]
+    pub mouse_timing: HotkeyTiming,
+    /// The replay speed of macros in percent.
+    #[serde(default = "default_macro_speed")]
+    pub macro_speed: u64,
+    /// How often a macro is replayed, 0 repeats it until stopped.
+    #[serde(default = "default_macro_loops")]
+    pub macro_loops: u64,
+    /// Runs that start at scheduled times.
+    #[serde(default)]
+    pub jobs: Vec<Job>,
+    /// Saved click settings of the app page.
+    #[serde(default)]
+    pub profiles: Vec<Profile>,
+    /// The name of the profile that was loaded last, empty if none was.
+    #[serde(default)]
+    pub profile: String,
+    /// The minutes of inactivity after which the keep-alive mode acts.
+    #[serde(default = "default_keepalive_minutes")]
+    pub keepalive_minutes: u64,
+    #[serde(default)]
+    pub keepalive_action: KeepaliveAction,
+    #[serde(default)]
+    pub idle_backend: IdleBackendKind,
+    #[serde(default)]
+    pub failsafe_corner: FailsafeCorner,
+    /// Safety limits for every run, 0 turns a limit off.
+    #[serde(default)]
+    pub max_clicks_per_second: u64,
+    #[serde(default)]
+    pub max_run_minutes: u64,
+    #[serde(default)]
+    pub max_session_clicks: u64,
 }
 
 impl State {
@@ -38,6 +101,45 @@ impl State {
             use_start_delay: global_state.get_use_start_delay(),
             use_duration: global_state.get_use_duration(),
             dark_mode: global_state.get_dark_mode(),
+            input_backend: parse_setting(&global_state.get_input_backend()),
+            hotkey_mode: parse_setting(&global_state.get_hotkey_mode()),
+            hotkey_backend: parse_setting(&global_state.get_hotkey_backend()),
+            hotkey_trigger: global_state.get_hotkey_trigger().into(),
+            hotkey_timing: parse_setting(&global_state.get_hotkey_timing()),
+            hold_timing: parse_setting(&global_state.get_hold_timing()),
+            mouse_trigger: parse_setting(&global_state.get_mouse_trigger()),
+            mouse_timing: parse_setting(&global_state.get_mouse_timing()),
+            macro_speed: global_state.get_macro_speed().try_into().unwrap(),
+            macro_loops: global_state.get_macro_loops().try_into().unwrap(),
+            jobs: global_state
+                .get_jobs()
+                .iter()
+                .map(|job| Job {
+                    schedule: job.schedule.into(),
+                    duration: (job.duration > 0).then_some(job.duration as u64),
+                    profile: job.profile.into(),
+                })
+                .collect(),
+            profiles: global_state
+                .get_profiles()
+                .iter()
+                .map(|profile| Profile {
+                    name: profile.name.into(),
+                    delay: profile.delay as u64,
+                    start_delay: profile.start_delay as u64,
+                    duration: profile.duration as u64,
+                    use_start_delay: profile.use_start_delay,
+                    use_duration: profile.use_duration,
+                })
+                .collect(),
+            profile: global_state.get_profile().into(),
+            keepalive_minutes: global_state.get_keepalive_minutes().try_into().unwrap(),
+            keepalive_action: parse_setting(&global_state.get_keepalive_action()),
+            idle_backend: parse_setting(&global_state.get_idle_backend()),
+            failsafe_corner: parse_setting(&global_state.get_failsafe_corner()),
+            max_clicks_per_second: global_state.get_max_clicks_per_second().try_into().unwrap(),
+            max_run_minutes: global_state.get_max_run_minutes().try_into().unwrap(),
+            max_session_clicks: global_state.get_max_session_clicks().try_into().unwrap(),
         }
     }
 
@@ -55,10 +157,31 @@ impl State {
             return Ok(None);
         };
         let file = fs::File::open(&path)?;
-        let state: State = serde_json::from_reader(file)?;
+        let mut state: State = serde_json::from_reader(file)?;
+        state.clamp();
         Ok(Some(state))
     }
 
+    /// Limit every value to the range allowed in the UI and CLI.
+    /// The file can be edited by hand, e.g. a delay of 0 would click without ever sleeping.
+    fn clamp(&mut self) {
+        self.delay = self.delay.clamp(20, 1000);
+        self.start_delay = self.start_delay.clamp(1, 60);
+        self.duration = self.duration.clamp(1, 60);
+        self.macro_speed = self.macro_speed.clamp(10, 1000);
+        self.macro_loops = self.macro_loops.min(1000);
+        self.keepalive_minutes = self.keepalive_minutes.clamp(1, 1440);
+        self.max_clicks_per_second = self.max_clicks_per_second.min(1000);
+        self.max_run_minutes = self.max_run_minutes.min(1440);
+        self.max_session_clicks = self.max_session_clicks.min(1_000_000);
+        for job in &mut self.jobs {
+            job.duration = job.duration.map(|duration| duration.min(86400));
+        }
+        for profile in &mut self.profiles {
+            profile.clamp();
+        }
+    }
+
     /// Update the GlobalState in the App with this State instance.
     pub fn update_app(&self, app: &AppWindow) {
         let global_state = app.global::<GlobalState>();
@@ -69,6 +192,82 @@ impl State {
         global_state.set_use_start_delay(self.use_start_delay);
         global_state.set_use_duration(self.use_duration);
         global_state.set_dark_mode(self.dark_mode);
+        global_state.set_input_backend(setting_str(&self.input_backend).into());
+        global_state.set_hotkey_mode(setting_str(&self.hotkey_mode).into());
+        global_state.set_hotkey_backend(setting_str(&self.hotkey_backend).into());
+        global_state.set_hotkey_trigger(self.hotkey_trigger.as_str().into());
+        global_state.set_hotkey_timing(setting_str(&self.hotkey_timing).into());
+        global_state.set_hold_timing(setting_str(&self.hold_timing).into());
+        global_state.set_mouse_trigger(setting_str(&self.mouse_trigger).into());
+        global_state.set_mouse_timing(setting_str(&self.mouse_timing).into());
+        global_state.set_macro_speed(self.macro_speed as i32);
+        global_state.set_macro_loops(self.macro_loops as i32);
+        // Only the scheduler fills in the next run, so unchanged jobs keep the one it has shown.
+        let shown = global_state.get_jobs();
+        let jobs: Vec<ScheduledJob> = self
+            .jobs
+            .iter()
+            .enumerate()
+            .map(|(index, job)| {
+                let schedule: slint::SharedString = job.schedule.as_str().into();
+                let duration = job.duration.unwrap_or_default() as i32;
+                let profile: slint::SharedString = job.profile.as_str().into();
+                let next_run = shown
+                    .row_data(index)
+                    .filter(|shown| {
+                        shown.schedule == schedule
+                            && shown.duration == duration
+                            && shown.profile == profile
+                    })
+                    .map(|shown| shown.next_run)
+                    .unwrap_or_default();
+                ScheduledJob {
+                    schedule,
+                    duration,
+                    profile,
+                    next_run,
+                }
+            })
+            .collect();
+        global_state.set_jobs(ModelRc::new(VecModel::from(jobs)));
+        set_profiles(app, &self.profiles);
+        global_state.set_profile(self.profile.as_str().into());
+        global_state.set_keepalive_minutes(self.keepalive_minutes as i32);
+        global_state.set_keepalive_action(setting_str(&self.keepalive_action).into());
+        global_state.set_idle_backend(setting_str(&self.idle_backend).into());
+        global_state.set_failsafe_corner(setting_str(&self.failsafe_corner).into());
+        global_state.set_max_clicks_per_second(self.max_clicks_per_second as i32);
+        global_state.set_max_run_minutes(self.max_run_minutes as i32);
+        global_state.set_max_session_clicks(self.max_session_clicks as i32);
+    }
+
+    /// The settings for runs started by a shortcut or the mouse button.
+    pub fn hotkey_settings(&self) -> HotkeySettings {
+        HotkeySettings {
+            mode: self.hotkey_mode,
+            hotkey: self.run_timing(self.hotkey_timing),
+            hold: self.run_timing(self.hold_timing),
+            mouse: self.run_timing(self.mouse_timing),
+        }
+    }
+
+    /// The start delay and duration that apply with the timing of a trigger.
+    fn run_timing(&self, timing: HotkeyTiming) -> RunTiming {
+        let use_start_delay = self.use_start_delay && timing == HotkeyTiming::Settings;
+        let use_duration = self.use_duration && timing != HotkeyTiming::Ignore;
+        RunTiming {
+            start_delay: use_start_delay.then_some(self.start_delay),
+            duration: use_duration.then_some(self.duration),
+        }
+    }
+
+    /// The safety limits for every run.
+    pub fn limits(&self) -> Limits {
+        Limits {
+            max_clicks_per_second: self.max_clicks_per_second,
+            max_run_minutes: self.max_run_minutes,
+            max_session_clicks: self.max_session_clicks,
+        }
     }
 
     /// Save the state to user specific state file.
@@ -81,8 +280,169 @@ impl State {
     }
 }
 
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            delay: 20,
+            start_delay: 1,
+            duration: 1,
+            use_start_delay: true,
+            use_duration: true,
+            dark_mode: true,
+            input_backend: Default::default(),
+            hotkey_mode: Default::default(),
+            hotkey_backend: Default::default(),
+            hotkey_trigger: String::new(),
+            hotkey_timing: Default::default(),
+            hold_timing: Default::default(),
+            mouse_trigger: Default::default(),
+            mouse_timing: Default::default(),
+            macro_speed: default_macro_speed(),
+            macro_loops: default_macro_loops(),
+            jobs: Vec::new(),
+            profiles: Vec::new(),
+            profile: String::new(),
+            keepalive_minutes: default_keepalive_minutes(),
+            keepalive_action: Default::default(),
+            idle_backend: Default::default(),
+            failsafe_corner: Default::default(),
+            max_clicks_per_second: 0,
+            max_run_minutes: 0,
+            max_session_clicks: 0,
+        }
+    }
+}
+
+fn default_macro_speed() -> u64 {
+    100
+}
+
+fn default_macro_loops() -> u64 {
+    1
+}
+
+fn default_keepalive_minutes() -> u64 {
+    5
+}
+
+/// Show the profiles in the app, their names are listed separately for selecting them.
+pub fn set_profiles(app: &AppWindow, profiles: &[Profile]) {
+    let global_state = app.global::<GlobalState>();
+    let names: Vec<slint::SharedString> = profiles
+        .iter()
+        .map(|profile| profile.name.as_str().into())
+        .collect();
+    let profiles: Vec<ClickProfile> = profiles
+        .iter()
+        .map(|profile| ClickProfile {
+            name: profile.name.as_str().into(),
+            delay: profile.delay as i32,
+            start_delay: profile.start_delay as i32,
+            duration: profile.duration as i32,
+            use_start_delay: profile.use_start_delay,
+            use_duration: profile.use_duration,
+        })
+        .collect();
+    global_state.set_profiles(ModelRc::new(VecModel::from(profiles)));
+    global_state.set_profile_names(ModelRc::new(VecModel::from(names)));
+}
+
+/// Parse a setting from its string representation in the UI.
+/// Falls back to the default for unknown values.
+fn parse_setting<T>(value: &str) -> T
+where
+    T: DeserializeOwned + Default,
+{
+    serde_json::from_value(serde_json::Value::String(value.to_string())).unwrap_or_default()
+}
+
+/// Convert a setting into its string representation in the UI.
+fn setting_str<T>(value: &T) -> String
+where
+    T: Serialize,
+{
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(value)) => value,
+        _ => String::new(),
+    }
+}
+
+/// Watch the user specific state file for changes made outside of the app.
+/// Calls on_change with the new state every time the file is written or replaced.
+/// Files that fail to parse are reported and ignored, the current state stays in place.
+pub fn watch_state_file<F>(on_change: F) -> Result<(), Box<dyn Error>>
+where
+    F: Fn(State) + Send + 'static,
+{
+    watch_path(get_state_file_path(), on_change)
+}
+
+/// Watch the given state file for changes, see watch_state_file.
+fn watch_path<P, F>(path: P, on_change: F) -> Result<(), Box<dyn Error>>
+where
+    P: AsRef<Path>,
+    F: Fn(State) + Send + 'static,
+{
+    let path = path.as_ref().to_path_buf();
+    let dir = path
+        .parent()
+        .ok_or("Failed to get parent directory")?
+        .to_path_buf();
+    let file_name = path
+        .file_name()
+        .ok_or("Failed to get file name")?
+        .to_os_string();
+    fs::create_dir_all(&dir)?;
+
+    let inotify = Inotify::init()?;
+    // Editors and dotfile managers often replace the file instead of writing to it,
+    // so watch the directory as well as the file itself (which follows symlinks).
+    let dir_watch = inotify
+        .watches()
+        .add(&dir, WatchMask::CLOSE_WRITE | WatchMask::MOVED_TO)?;
+    add_file_watch(inotify.watches(), &path);
+
+    let mut stream = inotify.into_event_stream([0; 1024])?;
+    tokio::spawn(async move {
+        while let Some(event) = stream.next().await {
+            let event = match event {
+                Ok(event) => event,
+                Err(e) => {
+                    eprintln!("Failed to read state file events: {e}");
+                    break;
+                }
+            };
+            if event.wd == dir_watch && event.name.as_ref() != Some(&file_name) {
+                continue;
+            }
+            add_file_watch(stream.watches(), &path);
+
+            match State::from_path(&path) {
+                Ok(Some(state)) => on_change(state),
+                Ok(None) => (),
+                Err(e) => eprintln!("Ignoring invalid state file '{}': {e}", path.display()),
+            }
+        }
+    });
+    Ok(())
+}
+
+/// (Re-)add the watch on the state file, needed after the file has been replaced.
+fn add_file_watch(mut watches: Watches, path: &Path) {
+    if fs::exists(path).unwrap_or(false)
+        && let Err(e) = watches.add(path, WatchMask::CLOSE_WRITE)
+    {
+        eprintln!("Failed to watch state file '{}': {e}", path.display());
+    }
+}
+
 /// Read the XDG state directory from the environment and return the full path to the state file.
 fn get_state_file_path() -> String {
+    format!("{}/state.json", get_state_dir())
+}
+
+/// Read the XDG state directory from the environment and return the directory of the app in it.
+pub fn get_state_dir() -> String {
     let mut path = match env::var(XDG_STATE_HOME) {
         Ok(path) if !path.is_empty() => Some(path),
         _ => None,
@@ -97,7 +457,7 @@ fn get_state_file_path() -> String {
 
     let path = path.unwrap_or(format!("./{XDG_STATE_HOME_DEFAULT}"));
 
-    format!("{path}/{XDG_STATE_HOME_DIR}/state.json")
+    format!("{path}/{XDG_STATE_HOME_DIR}")
 }
 
 /// Create the parent directory of the given file if it does not exist
@@ -1,5 +1,11 @@
 use super::*;
+use crate::failsafe::FailsafeCorner;
+use crate::hotkey::HotkeyAction;
+use crate::idle::{IdleBackendKind, KeepaliveAction};
 use serial_test::serial;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
 
 #[test]
 fn state_from_app() {
@@ -10,6 +16,44 @@ fn state_from_app() {
         use_start_delay: false,
         use_duration: true,
         dark_mode: true,
+        input_backend: BackendKind::Uinput,
+        hotkey_mode: HotkeyMode::Hold,
+        hotkey_backend: HotkeyBackendKind::X11,
+        hotkey_trigger: "ALT+F8".to_string(),
+        hotkey_timing: HotkeyTiming::Immediate,
+        hold_timing: HotkeyTiming::Ignore,
+        mouse_trigger: MouseTrigger::Back,
+        mouse_timing: HotkeyTiming::Immediate,
+        macro_speed: 50,
+        macro_loops: 3,
+        jobs: vec![
+            Job {
+                schedule: "every weekday 09:00".to_string(),
+                duration: Some(30),
+                profile: "fast".to_string(),
+            },
+            Job {
+                schedule: "at 2026-10-20 18:00".to_string(),
+                duration: None,
+                profile: String::new(),
+            },
+        ],
+        profiles: vec![Profile {
+            name: "fast".to_string(),
+            delay: 20,
+            start_delay: 2,
+            duration: 30,
+            use_start_delay: false,
+            use_duration: true,
+        }],
+        profile: "fast".to_string(),
+        keepalive_minutes: 10,
+        keepalive_action: KeepaliveAction::Click,
+        idle_backend: IdleBackendKind::Evdev,
+        failsafe_corner: FailsafeCorner::BottomRight,
+        max_clicks_per_second: 5,
+        max_run_minutes: 60,
+        max_session_clicks: 1000,
     };
 
     i_slint_backend_testing::init_no_event_loop();
@@ -21,6 +65,46 @@ fn state_from_app() {
     global_state.set_use_start_delay(expected_state.use_start_delay);
     global_state.set_use_duration(expected_state.use_duration);
     global_state.set_dark_mode(expected_state.dark_mode);
+    global_state.set_input_backend("uinput".into());
+    global_state.set_hotkey_mode("hold".into());
+    global_state.set_hotkey_backend("x11".into());
+    global_state.set_hotkey_trigger("ALT+F8".into());
+    global_state.set_hotkey_timing("immediate".into());
+    global_state.set_hold_timing("ignore".into());
+    global_state.set_mouse_trigger("back".into());
+    global_state.set_mouse_timing("immediate".into());
+    global_state.set_macro_speed(expected_state.macro_speed as i32);
+    global_state.set_macro_loops(expected_state.macro_loops as i32);
+    global_state.set_jobs(ModelRc::new(VecModel::from(vec![
+        ScheduledJob {
+            schedule: "every weekday 09:00".into(),
+            duration: 30,
+            profile: "fast".into(),
+            next_run: "Mon 2026-10-19 09:00".into(),
+        },
+        ScheduledJob {
+            schedule: "at 2026-10-20 18:00".into(),
+            duration: 0,
+            profile: Default::default(),
+            next_run: Default::default(),
+        },
+    ])));
+    global_state.set_profiles(ModelRc::new(VecModel::from(vec![ClickProfile {
+        name: "fast".into(),
+        delay: 20,
+        start_delay: 2,
+        duration: 30,
+        use_start_delay: false,
+        use_duration: true,
+    }])));
+    global_state.set_profile("fast".into());
+    global_state.set_keepalive_minutes(10);
+    global_state.set_keepalive_action("click".into());
+    global_state.set_idle_backend("evdev".into());
+    global_state.set_failsafe_corner("bottom-right".into());
+    global_state.set_max_clicks_per_second(5);
+    global_state.set_max_run_minutes(60);
+    global_state.set_max_session_clicks(1000);
 
     assert_eq!(
         expected_state,
@@ -29,6 +113,18 @@ fn state_from_app() {
     );
 }
 
+#[test]
+fn state_default_matches_app() {
+    i_slint_backend_testing::init_no_event_loop();
+    let app = AppWindow::new().expect("Should create AppWindow");
+
+    assert_eq!(
+        State::from_app(&app),
+        State::default(),
+        "Default should match the defaults of the UI"
+    );
+}
+
 #[test]
 fn state_update_app() {
     let state = State {
@@ -38,6 +134,37 @@ fn state_update_app() {
         use_start_delay: true,
         use_duration: false,
         dark_mode: false,
+        input_backend: BackendKind::Uinput,
+        hotkey_mode: HotkeyMode::Hold,
+        hotkey_backend: HotkeyBackendKind::Evdev,
+        hotkey_trigger: "ALT+F8".to_string(),
+        hotkey_timing: HotkeyTiming::Ignore,
+        hold_timing: HotkeyTiming::Immediate,
+        mouse_trigger: MouseTrigger::Forward,
+        mouse_timing: HotkeyTiming::Ignore,
+        macro_speed: 200,
+        macro_loops: 0,
+        jobs: vec![Job {
+            schedule: "every day 12:00".to_string(),
+            duration: Some(5),
+            profile: String::new(),
+        }],
+        profiles: vec![Profile {
+            name: "slow".to_string(),
+            delay: 500,
+            start_delay: 1,
+            duration: 10,
+            use_start_delay: true,
+            use_duration: false,
+        }],
+        profile: "slow".to_string(),
+        keepalive_minutes: 30,
+        keepalive_action: KeepaliveAction::Click,
+        idle_backend: IdleBackendKind::Mutter,
+        failsafe_corner: FailsafeCorner::TopRight,
+        max_clicks_per_second: 10,
+        max_run_minutes: 30,
+        max_session_clicks: 500,
     };
 
     i_slint_backend_testing::init_no_event_loop();
@@ -75,6 +202,157 @@ fn state_update_app() {
         global_state.get_dark_mode(),
         "GlobalState dark_mode should match State dark_mode"
     );
+    assert_eq!(
+        "uinput",
+        global_state.get_input_backend().as_str(),
+        "GlobalState input_backend should match State input_backend"
+    );
+    assert_eq!(
+        "hold",
+        global_state.get_hotkey_mode().as_str(),
+        "GlobalState hotkey_mode should match State hotkey_mode"
+    );
+    assert_eq!(
+        "evdev",
+        global_state.get_hotkey_backend().as_str(),
+        "GlobalState hotkey_backend should match State hotkey_backend"
+    );
+    assert_eq!(
+        "ALT+F8",
+        global_state.get_hotkey_trigger().as_str(),
+        "GlobalState hotkey_trigger should match State hotkey_trigger"
+    );
+    assert_eq!(
+        "ignore",
+        global_state.get_hotkey_timing().as_str(),
+        "GlobalState hotkey_timing should match State hotkey_timing"
+    );
+    assert_eq!(
+        "immediate",
+        global_state.get_hold_timing().as_str(),
+        "GlobalState hold_timing should match State hold_timing"
+    );
+    assert_eq!(
+        "forward",
+        global_state.get_mouse_trigger().as_str(),
+        "GlobalState mouse_trigger should match State mouse_trigger"
+    );
+    assert_eq!(
+        "ignore",
+        global_state.get_mouse_timing().as_str(),
+        "GlobalState mouse_timing should match State mouse_timing"
+    );
+    assert_eq!(
+        200,
+        global_state.get_macro_speed(),
+        "GlobalState macro_speed should match State macro_speed"
+    );
+    assert_eq!(
+        0,
+        global_state.get_macro_loops(),
+        "GlobalState macro_loops should match State macro_loops"
+    );
+    let jobs: Vec<ScheduledJob> = global_state.get_jobs().iter().collect();
+    assert_eq!(1, jobs.len(), "GlobalState jobs should match State jobs");
+    assert_eq!("every day 12:00", jobs[0].schedule.as_str());
+    assert_eq!(5, jobs[0].duration);
+    let profiles: Vec<ClickProfile> = global_state.get_profiles().iter().collect();
+    assert_eq!(1, profiles.len(), "GlobalState profiles should match State");
+    assert_eq!(500, profiles[0].delay);
+    let names: Vec<slint::SharedString> = global_state.get_profile_names().iter().collect();
+    assert_eq!(
+        vec![slint::SharedString::from("slow")],
+        names,
+        "Profile names should be listed"
+    );
+    assert_eq!("slow", global_state.get_profile().as_str());
+    assert_eq!(
+        30,
+        global_state.get_keepalive_minutes(),
+        "GlobalState keepalive_minutes should match State keepalive_minutes"
+    );
+    assert_eq!(
+        "click",
+        global_state.get_keepalive_action().as_str(),
+        "GlobalState keepalive_action should match State keepalive_action"
+    );
+    assert_eq!(
+        "mutter",
+        global_state.get_idle_backend().as_str(),
+        "GlobalState idle_backend should match State idle_backend"
+    );
+    assert_eq!(
+        "top-right",
+        global_state.get_failsafe_corner().as_str(),
+        "GlobalState failsafe_corner should match State failsafe_corner"
+    );
+    assert_eq!(
+        10,
+        global_state.get_max_clicks_per_second(),
+        "GlobalState max_clicks_per_second should match State max_clicks_per_second"
+    );
+    assert_eq!(
+        30,
+        global_state.get_max_run_minutes(),
+        "GlobalState max_run_minutes should match State max_run_minutes"
+    );
+    assert_eq!(
+        500,
+        global_state.get_max_session_clicks(),
+        "GlobalState max_session_clicks should match State max_session_clicks"
+    );
+}
+
+#[test]
+fn state_update_app_keeps_next_run_of_unchanged_jobs() {
+    i_slint_backend_testing::init_no_event_loop();
+    let app = AppWindow::new().expect("Should create AppWindow");
+    let global_state = app.global::<GlobalState>();
+    let shown = vec![
+        ScheduledJob {
+            schedule: "every day 12:00".into(),
+            duration: 5,
+            profile: Default::default(),
+            next_run: "Tue 2026-10-20 12:00".into(),
+        },
+        ScheduledJob {
+            schedule: "every weekday 09:00".into(),
+            duration: 0,
+            profile: Default::default(),
+            next_run: "Tue 2026-10-20 09:00".into(),
+        },
+    ];
+    global_state.set_jobs(ModelRc::new(VecModel::from(shown)));
+
+    let state = State {
+        jobs: vec![
+            Job {
+                schedule: "every day 12:00".to_string(),
+                duration: Some(5),
+                profile: String::new(),
+            },
+            Job {
+                schedule: "every weekend 10:00".to_string(),
+                duration: None,
+                profile: String::new(),
+            },
+        ],
+        delay: 300,
+        ..Default::default()
+    };
+    state.update_app(&app);
+
+    let jobs: Vec<ScheduledJob> = global_state.get_jobs().iter().collect();
+    assert_eq!(
+        "Tue 2026-10-20 12:00",
+        jobs[0].next_run.as_str(),
+        "Unchanged job should keep its next run"
+    );
+    assert_eq!(
+        "",
+        jobs[1].next_run.as_str(),
+        "Changed job should wait for the scheduler"
+    );
 }
 
 #[test]
@@ -112,6 +390,26 @@ fn state_from_file() {
         use_start_delay: true,
         use_duration: true,
         dark_mode: false,
+        input_backend: BackendKind::Enigo,
+        hotkey_mode: HotkeyMode::Toggle,
+        hotkey_backend: HotkeyBackendKind::Auto,
+        hotkey_trigger: String::new(),
+        hotkey_timing: HotkeyTiming::Settings,
+        hold_timing: HotkeyTiming::Settings,
+        mouse_trigger: MouseTrigger::Off,
+        mouse_timing: HotkeyTiming::Settings,
+        macro_speed: 100,
+        macro_loops: 1,
+        jobs: Vec::new(),
+        profiles: Vec::new(),
+        profile: String::new(),
+        keepalive_minutes: 5,
+        keepalive_action: KeepaliveAction::Nudge,
+        idle_backend: IdleBackendKind::Auto,
+        failsafe_corner: FailsafeCorner::TopLeft,
+        max_clicks_per_second: 0,
+        max_run_minutes: 0,
+        max_session_clicks: 0,
     };
 
     assert_eq!(
@@ -124,6 +422,30 @@ fn state_from_file() {
     }
 }
 
+#[test]
+fn state_from_path_clamps_values() {
+    let tmp_dir = tempfile::tempdir().expect("Should create temporary directory");
+    let path = tmp_dir.path().join("state.json");
+    fs::write(
+        &path,
+        r#"{"delay":0,"start_delay":0,"duration":5000,"use_start_delay":true,"use_duration":true,"dark_mode":false,"macro_speed":1,"macro_loops":99999,"keepalive_minutes":0,"max_clicks_per_second":5000,"jobs":[{"schedule":"every 1m","duration":999999}]}"#,
+    )
+    .expect("Should write state file");
+
+    let state = State::from_path(&path)
+        .expect("Should not fail")
+        .expect("Should return Some state");
+
+    assert_eq!(20, state.delay, "Delay should be clamped");
+    assert_eq!(1, state.start_delay, "Start delay should be clamped");
+    assert_eq!(60, state.duration, "Duration should be clamped");
+    assert_eq!(10, state.macro_speed, "Macro speed should be clamped");
+    assert_eq!(1000, state.macro_loops, "Macro loops should be clamped");
+    assert_eq!(1, state.keepalive_minutes, "Keep-alive should be clamped");
+    assert_eq!(1000, state.max_clicks_per_second);
+    assert_eq!(Some(86400), state.jobs[0].duration, "Job should be clamped");
+}
+
 #[test]
 #[serial]
 fn state_save_to_file() {
@@ -134,6 +456,26 @@ fn state_save_to_file() {
         use_start_delay: true,
         use_duration: true,
         dark_mode: true,
+        input_backend: BackendKind::Uinput,
+        hotkey_mode: HotkeyMode::Hold,
+        hotkey_backend: HotkeyBackendKind::X11,
+        hotkey_trigger: "ALT+F8".to_string(),
+        hotkey_timing: HotkeyTiming::Settings,
+        hold_timing: HotkeyTiming::Settings,
+        mouse_trigger: MouseTrigger::Off,
+        mouse_timing: HotkeyTiming::Settings,
+        macro_speed: 100,
+        macro_loops: 1,
+        jobs: Vec::new(),
+        profiles: Vec::new(),
+        profile: String::new(),
+        keepalive_minutes: 5,
+        keepalive_action: KeepaliveAction::Nudge,
+        idle_backend: IdleBackendKind::Auto,
+        failsafe_corner: FailsafeCorner::TopLeft,
+        max_clicks_per_second: 0,
+        max_run_minutes: 0,
+        max_session_clicks: 0,
     };
 
     let tmp_dir = tempfile::tempdir().expect("Should create temporary directory");
@@ -218,3 +560,248 @@ fn get_state_file_path_home_unset() {
         env::set_var(HOME, home_dir);
     }
 }
+
+#[tokio::test]
+async fn watch_path_reports_external_changes() {
+    let tmp_dir = tempfile::tempdir().expect("Should create temporary directory");
+    let path = tmp_dir.path().join(XDG_STATE_HOME_DIR).join("state.json");
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    watch_path(&path, move |state| {
+        tx.send(state).expect("Should send state");
+    })
+    .expect("Should watch state file");
+
+    let state = State {
+        delay: 120,
+        start_delay: 3,
+        duration: 4,
+        use_start_delay: false,
+        use_duration: true,
+        dark_mode: false,
+        input_backend: BackendKind::Enigo,
+        hotkey_mode: HotkeyMode::Toggle,
+        hotkey_backend: HotkeyBackendKind::Auto,
+        hotkey_trigger: String::new(),
+        hotkey_timing: HotkeyTiming::Settings,
+        hold_timing: HotkeyTiming::Settings,
+        mouse_trigger: MouseTrigger::Off,
+        mouse_timing: HotkeyTiming::Settings,
+        macro_speed: 100,
+        macro_loops: 1,
+        jobs: Vec::new(),
+        profiles: Vec::new(),
+        profile: String::new(),
+        keepalive_minutes: 5,
+        keepalive_action: KeepaliveAction::Nudge,
+        idle_backend: IdleBackendKind::Auto,
+        failsafe_corner: FailsafeCorner::TopLeft,
+        max_clicks_per_second: 0,
+        max_run_minutes: 0,
+        max_session_clicks: 0,
+    };
+    fs::write(&path, serde_json::to_string(&state).unwrap()).expect("Should write state file");
+
+    let changed = timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .expect("Should be notified about the change")
+        .expect("Channel should not be closed");
+    assert_eq!(state, changed, "Reported state should match written state");
+
+    // Replace the file like an editor would, the watcher should follow.
+    let replaced = State {
+        delay: 240,
+        ..state
+    };
+    let tmp_path = tmp_dir.path().join("state.json.tmp");
+    fs::write(&tmp_path, serde_json::to_string(&replaced).unwrap())
+        .expect("Should write temporary file");
+    fs::rename(&tmp_path, &path).expect("Should replace state file");
+
+    let changed = timeout(Duration::from_secs(5), async {
+        loop {
+            let state = rx.recv().await.expect("Channel should not be closed");
+            if state == replaced {
+                return state;
+            }
+        }
+    })
+    .await
+    .expect("Should be notified about the replaced file");
+    assert_eq!(
+        replaced, changed,
+        "Reported state should match replaced state"
+    );
+}
+
+#[tokio::test]
+async fn watch_path_ignores_invalid_files() {
+    let tmp_dir = tempfile::tempdir().expect("Should create temporary directory");
+    let path = tmp_dir.path().join("state.json");
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    watch_path(&path, move |state| {
+        tx.send(state).expect("Should send state");
+    })
+    .expect("Should watch state file");
+
+    fs::write(&path, "not json").expect("Should write state file");
+
+    assert!(
+        timeout(Duration::from_millis(500), rx.recv())
+            .await
+            .is_err(),
+        "Invalid state should not be reported"
+    );
+}
+
+#[test]
+fn parse_setting_falls_back_to_default() {
+    assert_eq!(
+        BackendKind::Uinput,
+        parse_setting::<BackendKind>("uinput"),
+        "Should parse known value"
+    );
+    assert_eq!(
+        BackendKind::Enigo,
+        parse_setting::<BackendKind>("unknown"),
+        "Should fall back to default for unknown value"
+    );
+    assert_eq!(
+        "uinput",
+        setting_str(&BackendKind::Uinput),
+        "Should convert to string"
+    );
+}
+
+#[test]
+fn hotkey_settings_apply_timing() {
+    let mut state = State {
+        delay: 100,
+        start_delay: 3,
+        duration: 10,
+        use_start_delay: true,
+        use_duration: true,
+        dark_mode: false,
+        input_backend: BackendKind::Enigo,
+        hotkey_mode: HotkeyMode::Hold,
+        hotkey_backend: HotkeyBackendKind::Auto,
+        hotkey_trigger: String::new(),
+        hotkey_timing: HotkeyTiming::Settings,
+        hold_timing: HotkeyTiming::Settings,
+        mouse_trigger: MouseTrigger::Off,
+        mouse_timing: HotkeyTiming::Settings,
+        macro_speed: 100,
+        macro_loops: 1,
+        jobs: Vec::new(),
+        profiles: Vec::new(),
+        profile: String::new(),
+        keepalive_minutes: 5,
+        keepalive_action: KeepaliveAction::Nudge,
+        idle_backend: IdleBackendKind::Auto,
+        failsafe_corner: FailsafeCorner::TopLeft,
+        max_clicks_per_second: 0,
+        max_run_minutes: 0,
+        max_session_clicks: 0,
+    };
+
+    let settings = state.hotkey_settings();
+    assert_eq!(HotkeyMode::Hold, settings.mode);
+    assert_eq!(
+        Some(3),
+        settings.hotkey.start_delay,
+        "Should use the start delay"
+    );
+    assert_eq!(
+        Some(10),
+        settings.hotkey.duration,
+        "Should use the duration"
+    );
+
+    state.use_start_delay = false;
+    assert_eq!(
+        None,
+        state.hotkey_settings().hotkey.start_delay,
+        "Disabled start delay should not apply"
+    );
+
+    state.use_start_delay = true;
+    state.hotkey_timing = HotkeyTiming::Immediate;
+    let settings = state.hotkey_settings();
+    assert_eq!(
+        None, settings.hotkey.start_delay,
+        "Should start immediately"
+    );
+    assert_eq!(
+        Some(10),
+        settings.hotkey.duration,
+        "Should still use the duration"
+    );
+
+    state.hotkey_timing = HotkeyTiming::Ignore;
+    let settings = state.hotkey_settings();
+    assert_eq!(
+        None, settings.hotkey.start_delay,
+        "Should ignore the start delay"
+    );
+    assert_eq!(None, settings.hotkey.duration, "Should ignore the duration");
+}
+
+#[test]
+fn hotkey_settings_timing_per_trigger() {
+    let state = State {
+        start_delay: 3,
+        duration: 10,
+        use_start_delay: true,
+        use_duration: true,
+        hotkey_mode: HotkeyMode::Hold,
+        hotkey_timing: HotkeyTiming::Settings,
+        hold_timing: HotkeyTiming::Ignore,
+        mouse_timing: HotkeyTiming::Immediate,
+        ..Default::default()
+    };
+    let settings = state.hotkey_settings();
+
+    let start = settings.timing(HotkeyAction::Start);
+    assert_eq!(
+        Some(3),
+        start.start_delay,
+        "Start should use the hotkey timing"
+    );
+    assert_eq!(
+        Some(10),
+        start.duration,
+        "Start should use the hotkey timing"
+    );
+
+    let hold = settings.timing(HotkeyAction::Toggle);
+    assert_eq!(
+        None, hold.start_delay,
+        "Held toggle should use the hold timing"
+    );
+    assert_eq!(
+        None, hold.duration,
+        "Held toggle should use the hold timing"
+    );
+
+    let mouse = settings.timing(HotkeyAction::MouseToggle);
+    assert_eq!(
+        None, mouse.start_delay,
+        "Mouse button should use the mouse timing"
+    );
+    assert_eq!(
+        Some(10),
+        mouse.duration,
+        "Mouse button should use the mouse timing"
+    );
+
+    let settings = HotkeySettings {
+        mode: HotkeyMode::Toggle,
+        ..settings
+    };
+    assert_eq!(
+        settings.hotkey,
+        settings.timing(HotkeyAction::Toggle),
+        "Toggle mode should use the hotkey timing"
+    );
+}
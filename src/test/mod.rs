@@ -1,6 +1,13 @@
 use super::*;
 use crate::autoclicker::Autoclicker;
+use crate::hotkey::{HotkeyMode, MouseTrigger};
+use crate::input::BackendKind;
+use crate::input::recording::{self, RecordingBackend};
+use enigo::Button;
 use serial_test::serial;
+use slint::Model;
+use std::cell::Cell;
+use std::rc::Rc;
 use std::sync::atomic::Ordering;
 use std::{env, time::Duration};
 use tokio::time::sleep;
@@ -16,7 +23,7 @@ fn test_init_global_state() {
     let global_state = app.global::<GlobalState>();
 
     assert_eq!(20, global_state.get_delay(), "Delay should be default");
-    init_global_state(&app);
+    init_global_state(&app, &Overrides::default());
     assert_eq!(
         500,
         global_state.get_delay(),
@@ -30,7 +37,7 @@ fn test_init_global_state() {
 
 #[tokio::test]
 async fn test_register_start_auto_click() {
-    let autoclicker = Autoclicker::new().expect("Failed to create autoclicker");
+    let autoclicker = Autoclicker::with_backend(RecordingBackend::new());
     let autoclicker_delay = Arc::new(AtomicU64::new(1200));
 
     i_slint_backend_testing::init_no_event_loop();
@@ -50,6 +57,7 @@ async fn test_register_start_auto_click() {
         autoclicker.is_running(),
         "Autoclicker should be running after callback"
     );
+    // The defaults wait 1 s before clicking and then click for 1 s.
     sleep(Duration::from_secs(2)).await; // 2100ms since start
     assert!(!autoclicker.is_running(), "Autoclicker should have stopped");
 }
@@ -66,9 +74,18 @@ fn test_register_settings_changed() {
     i_slint_backend_testing::init_no_event_loop();
     let app = AppWindow::new().expect("Should create AppWindow");
     let autoclicker_delay = Arc::new(AtomicU64::new(1000));
+    let hotkey_settings = Arc::new(Mutex::new(HotkeySettings::default()));
 
-    register_settings_changed(&app, autoclicker_delay.clone());
+    register_settings_changed(
+        &app,
+        autoclicker_delay.clone(),
+        hotkey_settings.clone(),
+        Overrides::default(),
+    );
 
+    app.global::<GlobalState>().set_hotkey_mode("hold".into());
+    app.global::<GlobalState>().set_use_duration(true);
+    app.global::<GlobalState>().set_duration(5);
     app.global::<GlobalState>().invoke_settings_changed();
 
     assert!(
@@ -82,6 +99,647 @@ fn test_register_settings_changed() {
         autoclicker_delay.load(Ordering::SeqCst),
         "Autoclicker delay should be updated"
     );
+    assert_eq!(
+        HotkeyMode::Hold,
+        hotkey_settings.lock().unwrap().mode,
+        "Hotkey mode should be updated"
+    );
+    assert_eq!(
+        Some(5),
+        hotkey_settings.lock().unwrap().hotkey.duration,
+        "Hotkey duration should be updated"
+    );
+
+    unsafe {
+        env::remove_var(state::XDG_STATE_HOME);
+    }
+}
+
+#[test]
+fn test_apply_external_state() {
+    i_slint_backend_testing::init_no_event_loop();
+    let app = AppWindow::new().expect("Should create AppWindow");
+    let autoclicker_delay = Arc::new(AtomicU64::new(20));
+    let hotkey_settings = Mutex::new(HotkeySettings::default());
+
+    let mut state = State::from_app(&app);
+    state.delay = 350;
+    state.use_duration = !state.use_duration;
+    state.hotkey_mode = HotkeyMode::Hold;
+
+    apply_external_state(
+        &app,
+        state,
+        &autoclicker_delay,
+        &hotkey_settings,
+        &Overrides::default(),
+    );
+
+    let global_state = app.global::<GlobalState>();
+    assert_eq!(
+        350,
+        global_state.get_delay(),
+        "Delay should be updated from external state"
+    );
+    assert_eq!(
+        350,
+        autoclicker_delay.load(Ordering::SeqCst),
+        "Autoclicker delay should be updated"
+    );
+    assert_eq!(
+        HotkeyMode::Hold,
+        hotkey_settings.lock().unwrap().mode,
+        "Hotkey mode should be updated"
+    );
+}
+
+#[test]
+fn test_apply_external_state_reinitializes_input() {
+    i_slint_backend_testing::init_no_event_loop();
+    let app = AppWindow::new().expect("Should create AppWindow");
+    let retried = Rc::new(Cell::new(false));
+    app.global::<GlobalState>().on_retry_input({
+        let retried = retried.clone();
+        move || retried.set(true)
+    });
+
+    let mut state = State::from_app(&app);
+    state.delay = 350;
+    apply_external_state(
+        &app,
+        state,
+        &AtomicU64::new(20),
+        &Mutex::new(HotkeySettings::default()),
+        &Overrides::default(),
+    );
+    assert!(
+        !retried.get(),
+        "Input should not be reinitialized for other settings"
+    );
+
+    let mut state = State::from_app(&app);
+    state.input_backend = BackendKind::Uinput;
+    apply_external_state(
+        &app,
+        state,
+        &AtomicU64::new(20),
+        &Mutex::new(HotkeySettings::default()),
+        &Overrides::default(),
+    );
+    assert!(
+        retried.get(),
+        "Input should be reinitialized when the backend changes"
+    );
+}
+
+#[tokio::test]
+async fn test_register_safety_changed() {
+    // The pointer of the backend stays in the top-left corner.
+    let mut autoclicker = Autoclicker::with_backend(RecordingBackend::new());
+    let delay = Arc::new(AtomicU64::new(20));
+
+    i_slint_backend_testing::init_no_event_loop();
+    let app = AppWindow::new().expect("Should create AppWindow");
+    let global_state = app.global::<GlobalState>();
+    global_state.set_failsafe_corner("off".into());
+    register_safety_changed(&app, autoclicker.clone());
+
+    autoclicker.autoclick(Arc::clone(&delay), None, None).await;
+    sleep(Duration::from_millis(100)).await;
+    assert!(autoclicker.is_running(), "Failsafe corner should be off");
+    autoclicker.stop();
+    autoclicker.wait_until_stopped().await;
+
+    global_state.set_failsafe_corner("top-left".into());
+    global_state.invoke_safety_changed();
+    autoclicker.autoclick(delay, None, None).await;
+    sleep(Duration::from_millis(100)).await;
+    assert!(
+        autoclicker.is_stopped(),
+        "Failsafe corner should stop the run"
+    );
+}
+
+#[tokio::test]
+async fn test_init_system_limits_with_bad_file() {
+    let tmp_dir = tempfile::tempdir().expect("Should create temporary directory");
+    let path = tmp_dir.path().join("limits.json");
+    std::fs::write(&path, "max_clicks_per_second = 10").expect("Should write file");
+    let mut autoclicker = Autoclicker::with_backend(RecordingBackend::new());
+
+    i_slint_backend_testing::init_no_event_loop();
+    let app = AppWindow::new().expect("Should create AppWindow");
+    let global_state = app.global::<GlobalState>();
+    init_system_limits(&app, &autoclicker, path.to_str().unwrap());
+
+    assert_ne!(
+        "",
+        global_state.get_system_limits_error().as_str(),
+        "The error should be shown"
+    );
+    assert!(
+        !autoclicker
+            .autoclick(Arc::new(AtomicU64::new(20)), None, None)
+            .await,
+        "Runs should be refused"
+    );
+    assert!(!autoclicker.is_running());
+}
+
+#[test]
+fn test_apply_external_state_reregisters_triggers() {
+    i_slint_backend_testing::init_no_event_loop();
+    let app = AppWindow::new().expect("Should create AppWindow");
+    let hotkeys_retried = Rc::new(Cell::new(0));
+    let mouse_retried = Rc::new(Cell::new(0));
+    app.global::<GlobalState>().on_retry_hotkeys({
+        let retried = hotkeys_retried.clone();
+        move || retried.set(retried.get() + 1)
+    });
+    app.global::<GlobalState>().on_retry_mouse_trigger({
+        let retried = mouse_retried.clone();
+        move || retried.set(retried.get() + 1)
+    });
+
+    let mut state = State::from_app(&app);
+    state.hotkey_trigger = "CTRL+F9".to_string();
+    apply_external_state(
+        &app,
+        state,
+        &AtomicU64::new(20),
+        &Mutex::new(HotkeySettings::default()),
+        &Overrides::default(),
+    );
+    assert_eq!(
+        1,
+        hotkeys_retried.get(),
+        "Hotkeys should be registered again"
+    );
+    assert_eq!(0, mouse_retried.get(), "Mouse trigger should not change");
+
+    let mut state = State::from_app(&app);
+    state.mouse_trigger = MouseTrigger::Back;
+    apply_external_state(
+        &app,
+        state,
+        &AtomicU64::new(20),
+        &Mutex::new(HotkeySettings::default()),
+        &Overrides::default(),
+    );
+    assert_eq!(1, hotkeys_retried.get(), "Hotkeys should not change");
+    assert_eq!(1, mouse_retried.get(), "Mouse trigger should listen again");
+}
+
+#[test]
+fn test_run_hotkey_action_changes_delay() {
+    i_slint_backend_testing::init_no_event_loop();
+    let app = AppWindow::new().expect("Should create AppWindow");
+    let global_state = app.global::<GlobalState>();
+
+    global_state.set_delay(100);
+    run_hotkey_action(&app, HotkeyAction::RaiseDelay);
+    assert_eq!(110, global_state.get_delay(), "Delay should be raised");
+    run_hotkey_action(&app, HotkeyAction::LowerDelay);
+    run_hotkey_action(&app, HotkeyAction::LowerDelay);
+    assert_eq!(90, global_state.get_delay(), "Delay should be lowered");
+
+    global_state.set_delay(MIN_DELAY);
+    run_hotkey_action(&app, HotkeyAction::LowerDelay);
+    assert_eq!(
+        MIN_DELAY,
+        global_state.get_delay(),
+        "Delay should not go below minimum"
+    );
+    global_state.set_delay(MAX_DELAY);
+    run_hotkey_action(&app, HotkeyAction::RaiseDelay);
+    assert_eq!(
+        MAX_DELAY,
+        global_state.get_delay(),
+        "Delay should not go above maximum"
+    );
+}
+
+#[test]
+fn test_profiles_switch_with_hotkey() {
+    i_slint_backend_testing::init_no_event_loop();
+    let app = AppWindow::new().expect("Should create AppWindow");
+    let global_state = app.global::<GlobalState>();
+    register_profiles(&app);
+
+    run_hotkey_action(&app, HotkeyAction::NextProfile);
+    assert_eq!(20, global_state.get_delay(), "Nothing to switch to yet");
+
+    global_state.set_delay(100);
+    global_state.invoke_save_profile("slow".into());
+    global_state.set_delay(30);
+    global_state.set_use_duration(false);
+    global_state.invoke_save_profile(" fast ".into());
+    assert_eq!(2, global_state.get_profile_names().row_count());
+    assert_eq!("fast", global_state.get_profile().as_str());
+
+    run_hotkey_action(&app, HotkeyAction::NextProfile);
+    assert_eq!("slow", global_state.get_profile().as_str());
+    assert_eq!(100, global_state.get_delay(), "Should load the delay");
+    assert!(global_state.get_use_duration(), "Should load the duration");
+
+    run_hotkey_action(&app, HotkeyAction::NextProfile);
+    assert_eq!("fast", global_state.get_profile().as_str());
+    assert_eq!(30, global_state.get_delay());
+    assert!(!global_state.get_use_duration());
+
+    global_state.invoke_delete_profile("fast".into());
+    assert_eq!(1, global_state.get_profile_names().row_count());
+    assert_eq!("", global_state.get_profile().as_str());
+    global_state.invoke_select_profile("slow".into());
+    assert_eq!(100, global_state.get_delay());
+}
+
+#[test]
+fn test_set_hotkey_bindings() {
+    i_slint_backend_testing::init_no_event_loop();
+    let app = AppWindow::new().expect("Should create AppWindow");
+
+    set_hotkey_bindings(&app, vec!["Start the autoclicker: not bound".to_string()]);
+
+    let hotkeys = app.global::<GlobalState>().get_hotkeys();
+    assert_eq!(1, hotkeys.row_count(), "Should show every binding");
+    assert_eq!(
+        "Start the autoclicker: not bound",
+        hotkeys.row_data(0).unwrap().as_str()
+    );
+}
+
+#[test]
+fn test_set_hotkey_status() {
+    i_slint_backend_testing::init_no_event_loop();
+    let app = AppWindow::new().expect("Should create AppWindow");
+    let global_state = app.global::<GlobalState>();
+
+    set_hotkey_status(&app, "Portal not found");
+    assert!(
+        !global_state.get_hotkeys_available(),
+        "Hotkeys should not be available"
+    );
+    assert_eq!("Portal not found", global_state.get_hotkey_error().as_str());
+
+    set_hotkey_status(&app, "");
+    assert!(
+        global_state.get_hotkeys_available(),
+        "Hotkeys should be available"
+    );
+    assert_eq!("", global_state.get_hotkey_error().as_str());
+}
+
+#[test]
+fn test_set_hotkey_connection() {
+    i_slint_backend_testing::init_no_event_loop();
+    let app = AppWindow::new().expect("Should create AppWindow");
+    let global_state = app.global::<GlobalState>();
+
+    set_hotkey_connection(
+        &app,
+        HotkeyStatus::Reconnecting {
+            attempt: 2,
+            error: "the session was closed".to_string(),
+        },
+    );
+    assert!(
+        !global_state.get_hotkeys_available(),
+        "Hotkeys should not be available while reconnecting"
+    );
+    assert_eq!(
+        "Lost the hotkey session (the session was closed), reconnecting (attempt 2)",
+        global_state.get_hotkey_connection().as_str()
+    );
+
+    set_hotkey_connection(&app, HotkeyStatus::Active);
+    assert!(global_state.get_hotkeys_available());
+    assert_eq!("", global_state.get_hotkey_connection().as_str());
+}
+
+#[test]
+fn test_set_input_status() {
+    i_slint_backend_testing::init_no_event_loop();
+    let app = AppWindow::new().expect("Should create AppWindow");
+    let global_state = app.global::<GlobalState>();
+
+    set_input_status(&app, "No display found");
+    assert!(!global_state.get_input_ready(), "Input should not be ready");
+    assert_eq!("No display found", global_state.get_input_error().as_str());
+
+    set_input_status(&app, "");
+    assert!(global_state.get_input_ready(), "Input should be ready");
+    assert_eq!("", global_state.get_input_error().as_str());
+}
+
+#[test]
+#[serial]
+fn test_init_global_state_with_overrides() {
+    unsafe {
+        env::set_var(state::XDG_STATE_HOME, "testdata");
+    }
+    i_slint_backend_testing::init_no_event_loop();
+    let app = AppWindow::new().expect("Should create AppWindow");
+    let global_state = app.global::<GlobalState>();
+
+    let overrides = Overrides {
+        delay: Some(100),
+        ..Default::default()
+    };
+    init_global_state(&app, &overrides);
+    assert_eq!(100, global_state.get_delay(), "Delay should be overridden");
+    assert_eq!(
+        60,
+        global_state.get_start_delay(),
+        "Start delay should be updated from state file"
+    );
+
+    unsafe {
+        env::remove_var(state::XDG_STATE_HOME);
+    }
+}
+
+#[test]
+#[serial]
+fn test_save_global_state_keeps_overrides_out_of_file() {
+    let tmp_dir = tempfile::tempdir().expect("Should create temporary directory");
+
+    unsafe {
+        env::set_var(state::XDG_STATE_HOME, tmp_dir.path());
+    }
+
+    i_slint_backend_testing::init_no_event_loop();
+    let app = AppWindow::new().expect("Should create AppWindow");
+    let saved = State::from_app(&app);
+    saved.save_to_file().expect("Should save state to file");
+
+    let overrides = Overrides {
+        delay: Some(100),
+        ..Default::default()
+    };
+    init_global_state(&app, &overrides);
+    let global_state = app.global::<GlobalState>();
+    global_state.set_start_delay(42);
+
+    save_global_state(&app, &overrides);
+    let state = State::from_file()
+        .expect("Should load state file")
+        .expect("State file should exist");
+    assert_eq!(
+        saved.delay, state.delay,
+        "Overridden delay should not be saved"
+    );
+    assert_eq!(42, state.start_delay, "Other settings should be saved");
+
+    let overrides = Overrides {
+        save_overrides: true,
+        ..overrides
+    };
+    save_global_state(&app, &overrides);
+    let state = State::from_file()
+        .expect("Should load state file")
+        .expect("State file should exist");
+    assert_eq!(100, state.delay, "Overridden delay should be saved");
+
+    unsafe {
+        env::remove_var(state::XDG_STATE_HOME);
+    }
+}
+
+#[test]
+#[serial]
+fn test_save_global_state_without_state_file() {
+    let tmp_dir = tempfile::tempdir().expect("Should create temporary directory");
+
+    unsafe {
+        env::set_var(state::XDG_STATE_HOME, tmp_dir.path());
+    }
+
+    i_slint_backend_testing::init_no_event_loop();
+    let app = AppWindow::new().expect("Should create AppWindow");
+    let overrides = Overrides {
+        delay: Some(100),
+        ..Default::default()
+    };
+    init_global_state(&app, &overrides);
+    app.global::<GlobalState>().set_start_delay(42);
+
+    save_global_state(&app, &overrides);
+    let state = State::from_file()
+        .expect("Should load state file")
+        .expect("State file should be created");
+    assert_eq!(
+        State::default().delay,
+        state.delay,
+        "Overridden delay should keep its default"
+    );
+    assert_eq!(42, state.start_delay, "Other settings should be saved");
+
+    unsafe {
+        env::remove_var(state::XDG_STATE_HOME);
+    }
+}
+
+#[test]
+fn test_set_clicker_status() {
+    i_slint_backend_testing::init_no_event_loop();
+    let app = AppWindow::new().expect("Should create AppWindow");
+    let global_state = app.global::<GlobalState>();
+
+    set_clicker_status(&app, autoclicker::Status::Countdown(3));
+    assert!(
+        global_state.get_clicker_running(),
+        "Countdown should count as running"
+    );
+    assert_eq!(
+        "Starting in 3 s",
+        global_state.get_clicker_status().as_str()
+    );
+
+    set_clicker_status(&app, autoclicker::Status::Aborted);
+    assert!(!global_state.get_clicker_running());
+    assert_eq!(
+        "Start delay aborted",
+        global_state.get_clicker_status().as_str(),
+        "Should show that the countdown was aborted"
+    );
+
+    set_clicker_status(&app, autoclicker::Status::Paused);
+    assert!(
+        global_state.get_clicker_running() && global_state.get_clicker_paused(),
+        "Paused run should still count as running"
+    );
+
+    set_clicker_status(&app, autoclicker::Status::Idle);
+    assert_eq!("", global_state.get_clicker_status().as_str());
+    assert!(!global_state.get_clicker_paused());
+}
+
+#[tokio::test]
+#[serial]
+async fn test_register_macros() {
+    let tmp_dir = tempfile::tempdir().expect("Should create temporary directory");
+    unsafe {
+        env::set_var(state::XDG_STATE_HOME, tmp_dir.path());
+    }
+    let recorded = macros::Macro {
+        events: vec![macros::MacroEvent {
+            delay_ms: 0,
+            action: macros::MacroAction::Move { dx: 1, dy: 2 },
+        }],
+    };
+    recorded.save("test").expect("Should save macro");
+
+    i_slint_backend_testing::init_no_event_loop();
+    let app = AppWindow::new().expect("Should create AppWindow");
+    let global_state = app.global::<GlobalState>();
+    let backend = RecordingBackend::new();
+    register_macros(&app, Autoclicker::with_backend(backend.clone()));
+
+    assert_eq!(
+        vec![SharedString::from("test")],
+        global_state.get_macros().iter().collect::<Vec<_>>(),
+        "Saved macros should be listed"
+    );
+
+    global_state.set_use_start_delay(false);
+    global_state.set_use_duration(false);
+    global_state.invoke_play_macro("test".into());
+    sleep(Duration::from_millis(50)).await;
+    assert_eq!(
+        vec![recording::InputEvent::Move(1, 2)],
+        backend.events(),
+        "Macro should be played"
+    );
+
+    global_state.invoke_play_macro("missing".into());
+    assert_ne!(
+        "",
+        global_state.get_macro_error().as_str(),
+        "Loading a missing macro should show an error"
+    );
+
+    global_state.invoke_delete_macro("test".into());
+    assert_eq!(0, global_state.get_macros().row_count());
+
+    unsafe {
+        env::remove_var(state::XDG_STATE_HOME);
+    }
+}
+
+#[test]
+fn test_script_editor_check() {
+    i_slint_backend_testing::init_no_event_loop();
+    let app = AppWindow::new().expect("Should create AppWindow");
+    let global_state = app.global::<GlobalState>();
+    register_script_editor(&app, Autoclicker::new());
+
+    global_state.set_script_text("click\nwait 10".into());
+    global_state.invoke_check_script();
+    assert!(global_state.get_script_failed(), "Check should fail");
+    assert!(
+        global_state.get_script_message().starts_with("line 2:"),
+        "Should show the line of the error, got \"{}\"",
+        global_state.get_script_message()
+    );
+
+    global_state.set_script_text("click\nwait 10ms".into());
+    global_state.invoke_check_script();
+    assert!(!global_state.get_script_failed(), "Check should succeed");
+}
+
+#[tokio::test]
+async fn test_register_scheduler() {
+    i_slint_backend_testing::init_no_event_loop();
+    let app = AppWindow::new().expect("Should create AppWindow");
+    let global_state = app.global::<GlobalState>();
+    register_scheduler(
+        &app,
+        Autoclicker::with_backend(RecordingBackend::new()),
+        Arc::new(AtomicU64::new(20)),
+    );
+    assert_eq!("", global_state.get_next_job().as_str());
+
+    global_state.invoke_add_job("sometimes".into(), 0, "".into());
+    assert_ne!(
+        "",
+        global_state.get_job_error().as_str(),
+        "Invalid schedule should show an error"
+    );
+    assert_eq!(0, global_state.get_jobs().row_count());
+
+    global_state.invoke_add_job("every day 09:00".into(), 30, "slow".into());
+    assert_eq!("", global_state.get_job_error().as_str());
+    let job = global_state
+        .get_jobs()
+        .row_data(0)
+        .expect("Job should be added");
+    assert_eq!(30, job.duration);
+    assert_eq!("slow", job.profile.as_str());
+    assert_ne!("never", job.next_run.as_str(), "Job should be scheduled");
+    assert_eq!(job.next_run, global_state.get_next_job());
+
+    global_state.invoke_remove_job(0);
+    assert_eq!(0, global_state.get_jobs().row_count());
+    assert_eq!("", global_state.get_next_job().as_str());
+}
+
+#[tokio::test(start_paused = true)]
+#[serial]
+async fn test_scheduled_job_runs_with_profile() {
+    let tmp_dir = tempfile::tempdir().expect("Should create temporary directory");
+    unsafe {
+        env::set_var(state::XDG_STATE_HOME, tmp_dir.path());
+    }
+    i_slint_backend_testing::init_no_event_loop();
+    let app = AppWindow::new().expect("Should create AppWindow");
+    let global_state = app.global::<GlobalState>();
+    let backend = RecordingBackend::new();
+    let autoclicker = Autoclicker::with_backend(backend.clone());
+    let autoclicker_delay = Arc::new(AtomicU64::new(20));
+    register_profiles(&app);
+    register_settings_changed(
+        &app,
+        Arc::clone(&autoclicker_delay),
+        Arc::new(Mutex::new(HotkeySettings::default())),
+        Overrides::default(),
+    );
+
+    global_state.set_delay(100);
+    global_state.set_use_start_delay(false);
+    global_state.set_use_duration(true);
+    global_state.set_duration(1);
+    global_state.invoke_save_profile("slow".into());
+    global_state.set_delay(20);
+    global_state.invoke_settings_changed();
+
+    let job = scheduler::Job {
+        schedule: "every day 09:00".to_string(),
+        duration: None,
+        profile: "slow".to_string(),
+    };
+    start_scheduled_job(
+        &app,
+        autoclicker.clone(),
+        Arc::clone(&autoclicker_delay),
+        job,
+    );
+    assert_eq!(100, global_state.get_delay(), "Profile should be loaded");
+    assert_eq!(100, autoclicker_delay.load(Ordering::SeqCst));
+
+    sleep(Duration::from_millis(1500)).await;
+    assert!(
+        !autoclicker.is_running(),
+        "Job should stop after the duration"
+    );
+    let clicks = backend.clicks(Button::Left);
+    assert!(clicks.len() > 1, "Job should click, got {}", clicks.len());
+    for pair in clicks.windows(2) {
+        assert!(
+            pair[1] - pair[0] >= Duration::from_millis(100),
+            "Job should click with the delay of the profile"
+        );
+    }
 
     unsafe {
         env::remove_var(state::XDG_STATE_HOME);